@@ -1,31 +1,117 @@
+use std::env;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader};
 
-fn calculate_fuel(mass: f64) -> f64 {
-    let own_fuel = (mass / 3.0).floor() - 2.0;
+/// Calculates the fuel required to launch a module of the given mass, without taking into account
+/// the mass of the fuel itself.
+fn fuel_for_module(mass: i64) -> i64 {
+    mass / 3 - 2
+}
+
+/// Calculates the fuel required to launch a module of the given mass, including the fuel needed
+/// to carry the fuel.
+fn calculate_fuel(mass: i64) -> i64 {
+    let own_fuel = fuel_for_module(mass);
 
-    if own_fuel < 0.0 {
-        return 0.0;
+    if own_fuel < 0 {
+        return 0;
     }
 
     return own_fuel + calculate_fuel(own_fuel);
 }
 
-// https://riptutorial.com/rust/example/4275/read-a-file-line-by-line
-fn main() {
-    // Open the file in read-only mode, ignoring errors.
-    let file = File::open("masses.txt").unwrap();
-    let reader = BufReader::new(file);
-    let mut modules = 0.0;
-    let mut fuel = 0.0;
+/// Returns the total fuel required for all the modules; first without, and then with, the fuel
+/// needed to carry the fuel.
+fn total_fuel(masses: &[i64]) -> (i64, i64) {
+    masses.iter().fold((0, 0), |(modules, fuel), &mass| {
+        (modules + fuel_for_module(mass), fuel + calculate_fuel(mass))
+    })
+}
+
+/// Reads one mass from each line of the `reader`, skipping blank lines.
+///
+/// Returns an error if a line cannot be read, or an error of kind [`io::ErrorKind::InvalidData`]
+/// naming the line number of the first line which isn't a number.
+fn read_masses<R: BufRead>(reader: R) -> Result<Vec<i64>, io::Error> {
+    let mut masses = Vec::new();
 
-    for (_, line) in reader.lines().enumerate() {
-        let mass = line.unwrap().parse::<f64>().unwrap();
+    // https://riptutorial.com/rust/example/4275/read-a-file-line-by-line
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
 
-        modules += (mass / 3.0).floor() - 2.0;
-        fuel += calculate_fuel(mass);
+        if line.is_empty() {
+            continue;
+        }
+
+        let mass = line.parse::<i64>().map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid mass {:?} on line {}: {}", line, index + 1, err),
+            )
+        })?;
+
+        masses.push(mass);
     }
 
+    Ok(masses)
+}
+
+fn main() -> Result<(), io::Error> {
+    // The path to the masses may be given as an argument, defaulting to masses.txt.
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "masses.txt".to_string());
+
+    let masses = read_masses(BufReader::new(File::open(path)?))?;
+    let (modules, fuel) = total_fuel(&masses);
+
     println!("Module mass: {}", modules);
     println!("Fuel required by modules: {}", fuel);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuel_for_module() {
+        assert_eq!(fuel_for_module(12), 2);
+        assert_eq!(fuel_for_module(14), 2);
+        assert_eq!(fuel_for_module(1969), 654);
+        assert_eq!(fuel_for_module(100756), 33583);
+    }
+
+    #[test]
+    fn test_calculate_fuel() {
+        assert_eq!(calculate_fuel(12), 2);
+        assert_eq!(calculate_fuel(14), 2);
+        assert_eq!(calculate_fuel(1969), 966);
+        assert_eq!(calculate_fuel(100756), 50346);
+
+        // Negative fuel is treated as zero.
+        assert_eq!(calculate_fuel(2), 0);
+        assert_eq!(calculate_fuel(8), 0);
+        assert_eq!(calculate_fuel(9), 1);
+    }
+
+    #[test]
+    fn test_read_masses() -> Result<(), io::Error> {
+        assert_eq!(read_masses("12\n\n1969\n".as_bytes())?, vec![12, 1969]);
+
+        let error = read_masses("12\n\n1969\nabc\n".as_bytes()).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("on line 4"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_total_fuel() {
+        assert_eq!(total_fuel(&[12, 1969]), (656, 968));
+        assert_eq!(total_fuel(&[]), (0, 0));
+    }
 }
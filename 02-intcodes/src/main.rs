@@ -1,76 +1,119 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::process;
-
-fn read_intcodes(path: &str) -> Vec<usize> {
-    let file = File::open(path).unwrap();
-    let mut reader = BufReader::new(file);
-
-    let mut first_line = String::new();
-    reader.read_line(&mut first_line).unwrap();
-
-    first_line
-        .trim()
-        .split(",")
-        .map(|intcode| intcode.parse::<usize>().unwrap())
-        .collect()
-}
+use std::io;
 
-fn positions(intcodes: &Vec<usize>, position: usize) -> (usize, usize, usize) {
-    let lop_pos = intcodes[position + 1];
-    let rop_pos = intcodes[position + 2];
-    let out_pos = intcodes[position + 3];
+extern crate intcode;
+use intcode::{IntcodeError, Program};
 
-    (lop_pos, rop_pos, out_pos)
-}
+/// Runs the program until it halts, returning the final state of its memory. Returns an error if
+/// the program contains an invalid instruction.
+fn run_program(intcodes: Vec<i64>) -> Result<Vec<i64>, IntcodeError> {
+    let len = intcodes.len();
+    let mut program = Program::new(intcodes);
 
-fn run_program(intcodes: Vec<usize>) -> Vec<usize> {
-    let mut intcodes = intcodes;
-    let mut position = 0;
+    program.run_capturing_output()?;
 
-    while position < intcodes.len() {
-        let code = intcodes[position];
+    Ok((0..len).map(|address| program.peek(address)).collect())
+}
 
-        match code {
-            1 => {
-                let (left, right, out) = positions(&intcodes, position);
-                intcodes[out] = intcodes[left] + intcodes[right];
+/// Searches for the noun and verb, each between 0 and 99, which cause the program to leave the
+/// `target` value at address 0. Returns the first pair found, or None if there is no such pair.
+///
+/// Returns an error if any noun and verb result in a program with an invalid instruction.
+fn find_noun_verb(intcodes: &[i64], target: i64) -> Result<Option<(i64, i64)>, IntcodeError> {
+    for noun in 0..100 {
+        for verb in 0..100 {
+            let mut intcodes = intcodes.to_vec();
 
-                position += 4;
-            }
-            2 => {
-                let (left, right, out) = positions(&intcodes, position);
-                intcodes[out] = intcodes[left] * intcodes[right];
+            intcodes[1] = noun;
+            intcodes[2] = verb;
 
-                position += 4;
-            }
-            99 => break,
-            anything => {
-                eprintln!("Unknown intcode: {}", anything);
-                process::exit(1);
+            let result = run_program(intcodes)?;
+
+            if result[0] == target {
+                return Ok(Some((noun, verb)));
             }
         }
     }
 
-    intcodes
+    Ok(None)
 }
 
-fn main() {
-    let intcodes = read_intcodes("intcodes.txt");
+fn main() -> Result<(), io::Error> {
+    let intcodes = intcode::load_intcodes_from_file("intcodes.txt")?;
 
-    for noun in 0..100 {
-        for verb in 0..100 {
-            let mut intcodes = intcodes.clone();
+    let found = find_noun_verb(&intcodes, 19690720)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
-            intcodes[1] = noun;
-            intcodes[2] = verb;
+    if let Some((noun, verb)) = found {
+        println!("Noun: {} Verb: {}", noun, verb);
+    }
 
-            let result = run_program(intcodes);
+    Ok(())
+}
 
-            if result[0] == 19690720 {
-                println!("Noun: {} Verb: {}", noun, verb);
-                break;
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_usize_and_i64_intcodes_run_identically() -> Result<(), io::Error> {
+        let as_usize: Vec<usize> = fs::read_to_string("intcodes.txt")?
+            .trim()
+            .split(',')
+            .map(|intcode| intcode.parse::<usize>().unwrap())
+            .collect();
+
+        let mut as_i64 = intcode::load_intcodes_from_file("intcodes.txt")?;
+
+        assert_eq!(
+            as_usize
+                .iter()
+                .map(|intcode| *intcode as i64)
+                .collect::<Vec<i64>>(),
+            as_i64
+        );
+
+        as_i64[1] = 12;
+        as_i64[2] = 2;
+
+        let result = run_program(as_i64).unwrap();
+
+        assert!(result.iter().all(|intcode| *intcode >= 0));
+        assert_eq!(result[0], 5_110_675);
+
+        assert_eq!(
+            run_program(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]),
+            Ok(vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_noun_verb() -> Result<(), io::Error> {
+        // Multiplies the values at the noun and verb addresses.
+        let intcodes = vec![2, 0, 0, 0, 99, 7, 11];
+
+        assert_eq!(find_noun_verb(&intcodes, 77), Ok(Some((5, 6))));
+        assert_eq!(find_noun_verb(&intcodes, 13), Ok(None));
+
+        let intcodes = intcode::load_intcodes_from_file("intcodes.txt")?;
+        assert_eq!(find_noun_verb(&intcodes, 19690720), Ok(Some((48, 47))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_opcode() {
+        assert_eq!(
+            run_program(vec![1, 0, 0, 0, 42, 99]),
+            Err(IntcodeError::UnknownOpcode(42))
+        );
+
+        // The sum of the noun and verb is written over the halt instruction.
+        assert_eq!(
+            find_noun_verb(&[1101, 0, 0, 4, 99], 0),
+            Err(IntcodeError::UnknownOpcode(0))
+        );
     }
 }
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
+use std::env;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader};
 use std::ops::Deref;
 
 struct Token {
@@ -45,7 +46,8 @@ impl<'a> Wire {
                 x += vector.0;
                 y += vector.1;
 
-                wire_info.insert(Position(x, y), count);
+                // Keep the step count from the first visit when the wire crosses itself.
+                wire_info.entry(Position(x, y)).or_insert(count);
             }
         }
 
@@ -74,12 +76,36 @@ impl Deref for Wire {
     }
 }
 
-/// Reads the file at `path`, parsing each non-blank line into a vector of `[Token]` describing the
-/// path of the line.
-fn read_wires(path: &str) -> Vec<Vec<Token>> {
-    let file = File::open(path).unwrap();
-    let reader = BufReader::new(file);
+/// Given any number of `[Wire]`s, returns a vector of all positions occupied by two or more of the
+/// wires.
+fn all_intersections(wires: &[Wire]) -> Vec<&Position> {
+    let mut occupied = HashMap::new();
+
+    for wire in wires {
+        for position in wire.keys() {
+            *occupied.entry(position).or_insert(0) += 1;
+        }
+    }
+
+    occupied
+        .into_iter()
+        .filter(|(_, count)| *count >= 2)
+        .map(|(position, _)| position)
+        .collect()
+}
+
+/// Opens the wire file at `path` for reading, or reads from stdin when the path is "-".
+fn open_wires(path: &str) -> io::Result<Box<dyn BufRead>> {
+    if path == "-" {
+        Ok(Box::new(BufReader::new(io::stdin())))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
 
+/// Reads from the `reader`, parsing each non-blank line into a vector of `[Token]` describing the
+/// path of the line.
+fn read_wires(reader: &mut dyn BufRead) -> Vec<Vec<Token>> {
     reader
         .lines()
         .map(|line| line.unwrap())
@@ -127,6 +153,7 @@ fn min_distance(positions: &Vec<&Position>) -> Option<isize> {
 /// Takes a vector of known intersections between two wires with the two hashmaps representing the
 /// wires and returns the minimum number of steps to traverse from an intersection between the two
 /// wires back to the central port at (0, 0).
+#[cfg(test)]
 fn min_steps(positions: &Vec<&Position>, wire_one: &Wire, wire_two: &Wire) -> Option<isize> {
     if positions.len() == 0 {
         return None;
@@ -145,19 +172,101 @@ fn min_steps(positions: &Vec<&Position>, wire_one: &Wire, wire_two: &Wire) -> Op
     Some(min)
 }
 
-fn main() {
-    let wires = read_wires("wires.txt");
+/// Finds the intersections between two wires, returning both the minimum Manhattan distance from
+/// an intersection to the central port, and the minimum combined number of steps along the wires to
+/// reach an intersection. Returns None if the wires do not intersect.
+fn best_intersection(wire_one: &Wire, wire_two: &Wire) -> Option<(isize, isize)> {
+    let mut best = None;
+
+    for position in wire_one.intersection(wire_two) {
+        let distance = position.0.abs() + position.1.abs();
+        let steps = wire_one[position] + wire_two[position];
+
+        best = match best {
+            None => Some((distance, steps)),
+            Some((min_distance, min_steps)) => {
+                Some((distance.min(min_distance), steps.min(min_steps)))
+            }
+        };
+    }
+
+    best
+}
+
+/// Draws the two wires on the panel, with "o" at the central port, "X" where the wires intersect,
+/// and "#" and "+" for the first and second wire respectively.
+fn render(wire_one: &Wire, wire_two: &Wire) -> String {
+    // Bounds start at the central port so that it is always drawn.
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (0, 0, 0, 0);
+
+    for position in wire_one.keys().chain(wire_two.keys()) {
+        min_x = min_x.min(position.0);
+        max_x = max_x.max(position.0);
+        min_y = min_y.min(position.1);
+        max_y = max_y.max(position.1);
+    }
+
+    let mut output = String::new();
+
+    // Up is positive, so the highest row is drawn first.
+    for y in (min_y..=max_y).rev() {
+        for x in min_x..=max_x {
+            let position = Position(x, y);
+
+            let on_one = wire_one.contains_key(&position);
+            let on_two = wire_two.contains_key(&position);
+
+            output.push(match (on_one, on_two) {
+                _ if x == 0 && y == 0 => 'o',
+                (true, true) => 'X',
+                (true, false) => '#',
+                (false, true) => '+',
+                (false, false) => '.',
+            });
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // The wires are drawn to the console when given the --render flag.
+    let render_wires = args.iter().any(|arg| arg == "--render");
+
+    // The path to the wire file may be given as an argument, defaulting to wires.txt.
+    let path = args
+        .iter()
+        .find(|arg| *arg != "--render")
+        .map_or("wires.txt", |arg| arg.as_str());
+
+    let wires: Vec<Wire> = read_wires(&mut open_wires(path)?)
+        .iter()
+        .map(Wire::from_tokens)
+        .collect();
 
-    let wire_one = Wire::from_tokens(&wires[0]);
-    let wire_two = Wire::from_tokens(&wires[1]);
+    let (wire_one, wire_two) = (&wires[0], &wires[1]);
 
-    let intersections = wire_one.intersection(&wire_two); //wire_intersections(&wire_one, &wire_two);
+    if render_wires {
+        println!("{}", render(wire_one, wire_two));
+    }
+
+    let (distance, steps) =
+        best_intersection(wire_one, wire_two).expect("Expected the wires to intersect");
+
+    println!("distance: {} steps: {}", distance, steps);
 
-    println!(
-        "distance: {} steps: {}",
-        min_distance(&intersections).unwrap(),
-        min_steps(&intersections, &wire_one, &wire_two).unwrap()
-    );
+    if wires.len() > 2 {
+        println!(
+            "distance between any wires: {}",
+            min_distance(&all_intersections(&wires)).unwrap()
+        );
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -190,6 +299,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_render() {
+        let wire_one = Wire::from_tokens(&string_to_tokens("R8,U5,L5,D3"));
+        let wire_two = Wire::from_tokens(&string_to_tokens("U7,R6,D4,L4"));
+
+        let rendered = render(&wire_one, &wire_two);
+        let rows: Vec<&str> = rendered.lines().collect();
+
+        // Rows are drawn from y = 7 down to y = 0, so the origin is on the last.
+        assert_eq!(rows.len(), 8);
+        assert_eq!(rows[7], "o########");
+        assert_eq!(rows[0], "+++++++..");
+        assert_eq!(&rows[7 - 3][3..4], "X");
+        assert_eq!(&rows[7 - 5][6..7], "X");
+    }
+
+    #[test]
+    fn test_read_wires() {
+        let mut reader = io::Cursor::new("R8,U5,L5,D3\n\nU7,R6,D4,L4\n");
+        let wires = read_wires(&mut reader);
+
+        assert_eq!(wires.len(), 2);
+
+        let first: Vec<(char, usize)> = wires[0]
+            .iter()
+            .map(|token| (token.direction, token.length))
+            .collect();
+
+        assert_eq!(first, vec![('R', 8), ('U', 5), ('L', 5), ('D', 3)]);
+        assert_eq!(wires[1].len(), 4);
+    }
+
+    #[test]
+    fn test_self_crossing_wire() {
+        let wire = Wire::from_tokens(&string_to_tokens("R4,U2,L2,D4"));
+
+        assert_eq!(wire[&Position(2, 0)], 2);
+        assert_eq!(wire[&Position(2, -2)], 12);
+    }
+
+    #[test]
+    fn test_all_intersections() {
+        let wires = vec![
+            Wire::from_tokens(&string_to_tokens("R8,U5,L5,D3")),
+            Wire::from_tokens(&string_to_tokens("U7,R6,D4,L4")),
+            Wire::from_tokens(&string_to_tokens("U2,R10")),
+        ];
+
+        let mut intersections: Vec<(isize, isize)> = all_intersections(&wires)
+            .iter()
+            .map(|position| (position.0, position.1))
+            .collect();
+
+        intersections.sort();
+
+        assert_eq!(
+            intersections,
+            vec![(0, 1), (0, 2), (3, 2), (3, 3), (6, 5), (8, 2)]
+        );
+    }
+
+    #[test]
+    fn test_best_intersection() {
+        let wire_one = Wire::from_tokens(&string_to_tokens("R75,D30,R83,U83,L12,D49,R71,U7,L72"));
+        let wire_two = Wire::from_tokens(&string_to_tokens("U62,R66,U55,R34,D71,R55,D58,R83"));
+
+        assert_eq!(best_intersection(&wire_one, &wire_two), Some((159, 610)));
+
+        let wire_three = Wire::from_tokens(&string_to_tokens("D10"));
+
+        assert_eq!(best_intersection(&wire_one, &wire_three), None);
+    }
+
     #[test]
     fn test_part_two_example_one() {
         let wire_one = Wire::from_tokens(&string_to_tokens("R75,D30,R83,U83,L12,D49,R71,U7,L72"));
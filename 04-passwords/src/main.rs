@@ -1,12 +1,17 @@
 use std::env;
 use std::process;
 
+extern crate rayon;
+use rayon::prelude::*;
+
+/// The number of passwords checked by each task when searching in parallel.
+const CHUNK_SIZE: i32 = 10_000;
+
 /// Converts an i32 to a vector of its individual digits.
 fn number_to_vec(n: i32) -> Vec<i32> {
-    // All numbers are six digits.
-    assert!(n < 999999);
+    let length = std::iter::successors(Some(n), |n| Some(n / 10).filter(|n| *n > 0)).count();
 
-    let mut digits = Vec::with_capacity(6);
+    let mut digits = Vec::with_capacity(length);
     let mut n = n;
 
     while n > 9 {
@@ -19,27 +24,39 @@ fn number_to_vec(n: i32) -> Vec<i32> {
     digits
 }
 
+/// Returns each number in the range which is a valid password. If `exact` is true, passwords must
+/// contain a pair of consecutive digits which is not part of a longer run.
+fn valid_passwords(range: std::ops::Range<i32>, exact: bool) -> Vec<i32> {
+    range
+        .filter(|number| {
+            let digits = number_to_vec(*number);
+            check_increments(&digits) && check_double(&digits, exact)
+        })
+        .collect()
+}
+
 /// Checks a range of numbers to find the total number of valid password for part one and two of
 /// the challenge. Returns a 2-element tuple with the answers for part one and two respectively.
 fn check_password(range: std::ops::Range<i32>) -> (i32, i32) {
-    let mut doubles = 0;
-    let mut doubles_exact = 0;
-
-    for number in range {
-        let digits = number_to_vec(number);
+    let doubles = valid_passwords(range.clone(), false).len();
+    let doubles_exact = valid_passwords(range, true).len();
 
-        if check_increments(&digits) {
-            if check_double(&digits, false) {
-                doubles += 1;
-            }
-
-            if check_double(&digits, true) {
-                doubles_exact += 1;
-            }
-        }
-    }
+    (doubles as i32, doubles_exact as i32)
+}
 
-    (doubles, doubles_exact)
+/// Checks a range of numbers in the same way as `check_password`, but splits the range into chunks
+/// which are checked in parallel, and then combines the totals.
+fn check_password_parallel(range: std::ops::Range<i32>) -> (i32, i32) {
+    let chunks: Vec<std::ops::Range<i32>> = range
+        .clone()
+        .step_by(CHUNK_SIZE as usize)
+        .map(|start| start..start.saturating_add(CHUNK_SIZE).min(range.end))
+        .collect();
+
+    chunks
+        .into_par_iter()
+        .map(check_password)
+        .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
 }
 
 /// Checks the vector of digits for consecutive numbers. If `exact` is true, only two consecutive
@@ -108,7 +125,7 @@ fn main() {
         process::exit(1);
     }
 
-    let (doubles, doubles_exact) = check_password(range.unwrap());
+    let (doubles, doubles_exact) = check_password_parallel(range.unwrap());
     println!("Part 1: {}  Part 2: {}", doubles, doubles_exact);
 }
 
@@ -116,6 +133,13 @@ fn main() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_number_to_vec() {
+        assert_eq!(number_to_vec(0), vec![0]);
+        assert_eq!(number_to_vec(12345), vec![1, 2, 3, 4, 5]);
+        assert_eq!(number_to_vec(1234567), vec![1, 2, 3, 4, 5, 6, 7]);
+    }
+
     #[test]
     fn test_check_doublet() {
         assert!(check_double(&vec![1, 1, 2, 3, 4, 5], false));
@@ -150,6 +174,19 @@ mod tests {
         assert!(!check_increments(&vec![1, 2, 3, 4, 5, 4]));
     }
 
+    #[test]
+    fn test_valid_passwords() {
+        assert_eq!(
+            valid_passwords(111110..111125, false),
+            vec![
+                111111, 111112, 111113, 111114, 111115, 111116, 111117, 111118, 111119, 111122,
+                111123, 111124
+            ]
+        );
+
+        assert_eq!(valid_passwords(111110..111125, true), vec![111122]);
+    }
+
     #[test]
     fn test_part_one() {
         let (part_one, _) = check_password(307237..769058);
@@ -161,4 +198,10 @@ mod tests {
         let (_, part_two) = check_password(307237..769058);
         assert_eq!(part_two, 589);
     }
+
+    #[test]
+    fn test_check_password_parallel() {
+        assert_eq!(check_password_parallel(307237..769058), (889, 589));
+        assert_eq!(check_password_parallel(111110..111125), (12, 1));
+    }
 }
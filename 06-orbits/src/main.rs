@@ -10,6 +10,29 @@
 /// resolve.
 use std::collections::HashMap;
 use std::fs;
+use std::io;
+
+/// Describes why orbits in a System could not be traversed.
+#[derive(Debug, PartialEq)]
+enum OrbitError {
+    /// A body with the given name was expected, but does not exist in the system.
+    UnknownBody(String),
+    /// A body indirectly orbits itself. Contains the names of the bodies which form the cycle.
+    Cycle(Vec<String>),
+}
+
+/// Returns a Cycle error if `body` is already present in the `chain` of bodies being traversed.
+fn check_cycle(chain: &[&Body], body: &Body) -> Result<(), OrbitError> {
+    match chain.iter().position(|other| other.name == body.name) {
+        Some(index) => Err(OrbitError::Cycle(
+            chain[index..]
+                .iter()
+                .map(|body| body.name.clone())
+                .collect(),
+        )),
+        None => Ok(()),
+    }
+}
 
 /// Contains all the bodies in the system.
 struct System {
@@ -42,35 +65,97 @@ impl System {
         System { bodies }
     }
 
+    /// Calculates the total number of direct and indirect orbits of every body in the system. The
+    /// number of orbits of each body is remembered so that it is calculated only once, rather than
+    /// once for each body which orbits it.
+    fn total_orbits(&self) -> Result<usize, OrbitError> {
+        let mut depths = HashMap::with_capacity(self.bodies.len());
+
+        self.bodies
+            .values()
+            .map(|body| body.num_orbits(self, &mut depths))
+            .sum()
+    }
+
+    /// Returns the body called `name`, or an UnknownBody error if no such body exists.
+    fn body(&self, name: &str) -> Result<&Body, OrbitError> {
+        self.bodies
+            .get(name)
+            .ok_or_else(|| OrbitError::UnknownBody(String::from(name)))
+    }
+
     /// Find the number of transfer orbits required to move from orbiting the `source` body to the
     /// `target`. This is done by building a HashMap where each key is a parent key of the source
     /// Body and each each value the number of transfer orbits required, then iterating through the
     /// target Body parents until a common ancestor is found.
-    fn transfer_distance(&self, source: &Body, target: &Body) -> Option<usize> {
+    fn transfer_distance(&self, source: &Body, target: &Body) -> Result<Option<usize>, OrbitError> {
         let mut source_parents = HashMap::new();
-        let mut parent = Some(source);
-        let mut distance: usize = 0;
 
-        while let Some(p) = parent {
-            source_parents.insert(p.name.clone(), distance);
+        for (distance, body) in std::iter::once(source)
+            .chain(self.ancestors(&source.name)?)
+            .enumerate()
+        {
+            source_parents.insert(body.name.clone(), distance);
+        }
 
-            distance += 1;
-            parent = p.parent(self);
+        for (distance, body) in std::iter::once(target)
+            .chain(self.ancestors(&target.name)?)
+            .enumerate()
+        {
+            if let Some(source_distance) = source_parents.get(&body.name) {
+                return Ok(Some(source_distance + distance));
+            }
         }
 
-        let mut target_pointer = Some(target);
-        let mut distance = 0;
+        Ok(None)
+    }
 
-        while let Some(t) = target_pointer {
-            if source_parents.contains_key(&t.name) {
-                return Some(source_parents.get(&t.name).unwrap() + distance);
+    /// Returns the names of the bodies visited when transferring from the body orbited by `source`
+    /// to the body orbited by `target`: up through the ancestors of the source to the closest common
+    /// ancestor, then back down to the parent of the target. Returns None if the bodies share no
+    /// common ancestor.
+    fn transfer_path(
+        &self,
+        source: &Body,
+        target: &Body,
+    ) -> Result<Option<Vec<String>>, OrbitError> {
+        let up = self.ancestors(&source.name)?;
+        let down = self.ancestors(&target.name)?;
+
+        for (index, body) in up.iter().enumerate() {
+            if let Some(down_index) = down.iter().position(|other| other.name == body.name) {
+                return Ok(Some(
+                    up[..=index]
+                        .iter()
+                        .chain(down[..down_index].iter().rev())
+                        .map(|body| body.name.clone())
+                        .collect(),
+                ));
             }
+        }
+
+        Ok(None)
+    }
 
-            distance += 1;
-            target_pointer = t.parent(self);
+    /// Returns every body orbited -- directly or indirectly -- by the body called `name`, starting
+    /// with its direct parent and ending at the center of mass. Returns an empty vector if the body
+    /// orbits nothing, and an error if the body or one of its ancestors does not exist, or if the
+    /// body orbits itself.
+    fn ancestors(&self, name: &str) -> Result<Vec<&Body>, OrbitError> {
+        let mut chain = match self.bodies.get(name) {
+            Some(body) => vec![body],
+            None => return Err(OrbitError::UnknownBody(name.to_string())),
+        };
+
+        let mut parent = chain[0].parent(self)?;
+
+        while let Some(p) = parent {
+            check_cycle(&chain, p)?;
+            chain.push(p);
+            parent = p.parent(self)?;
         }
 
-        None
+        Ok(chain.split_off(1))
     }
 }
 
@@ -98,21 +183,50 @@ impl Body {
         }
     }
 
-    /// Returns the Body which is orbited by this Body. Returns None if the Body has no parent.
-    fn parent<'a>(&self, system: &'a System) -> Option<&'a Body> {
+    /// Returns the Body which is orbited by this Body. Returns None if the Body has no parent, and
+    /// an UnknownBody error if the parent is not in the system.
+    fn parent<'a>(&self, system: &'a System) -> Result<Option<&'a Body>, OrbitError> {
         match &self.parent_key {
-            None => None,
-            Some(parent_key) => system.bodies.get(parent_key),
+            None => Ok(None),
+            Some(parent_key) => system.body(parent_key).map(Some),
         }
     }
 
     /// Calculates the number of direct and indirect orbits. The body orbits its parent directly,
     /// and the parent of its parents (and so on...) indirectly.
-    fn num_orbits(&self, system: &System) -> usize {
-        match &self.parent_key {
-            Some(_) => 1 + self.parent(&system).unwrap().num_orbits(&system),
-            None => 0,
+    ///
+    /// The `depths` HashMap contains the number of orbits of bodies which have already been
+    /// calculated, and is updated with those calculated by this call. The parents are followed
+    /// until reaching one whose number of orbits is known or the center of mass, returning a Cycle
+    /// error if a body is seen twice.
+    fn num_orbits(
+        &self,
+        system: &System,
+        depths: &mut HashMap<String, usize>,
+    ) -> Result<usize, OrbitError> {
+        let mut chain = Vec::new();
+        let mut next = Some(self);
+        let mut known_depth = None;
+
+        while let Some(body) = next {
+            if let Some(depth) = depths.get(&body.name) {
+                known_depth = Some(*depth);
+                break;
+            }
+
+            check_cycle(&chain, body)?;
+            chain.push(body);
+            next = body.parent(system)?;
+        }
+
+        // The last body in the chain orbits one with a known depth, or is the center of mass.
+        let first_depth = known_depth.map_or(0, |depth| depth + 1);
+
+        for (offset, body) in chain.iter().rev().enumerate() {
+            depths.insert(body.name.clone(), first_depth + offset);
         }
+
+        Ok(depths[&self.name])
     }
 
     fn set_parent(&mut self, parent_key: String) {
@@ -120,34 +234,204 @@ impl Body {
     }
 }
 
-fn main() {
-    let data = fs::read_to_string("data/orbits.txt").unwrap();
-    let data = data.trim();
-
-    let data: Vec<(&str, &str)> = data
+/// Parses orbit data, where each line contains the name of a body, a ")", and the name of a body
+/// which orbits it. Returns each (center, orbiter) pair.
+fn parse_orbits(input: &str) -> Vec<(&str, &str)> {
+    input
+        .trim()
         .lines()
         .map(|line| {
-            let mut parts = line.split(")");
+            let mut parts = line.trim().split(')');
             (parts.next().unwrap(), parts.next().unwrap())
         })
-        .collect();
+        .collect()
+}
 
-    let system = System::new_with_data(data);
+/// Reads the orbit data in the file at the given `path` and constructs a System.
+fn load_system(path: &str) -> io::Result<System> {
+    let data = fs::read_to_string(path)?;
+
+    Ok(System::new_with_data(parse_orbits(&data)))
+}
+
+fn main() -> Result<(), OrbitError> {
+    let system = load_system("data/orbits.txt").expect("Failed to read data/orbits.txt");
 
     println!(
         "Total direct and indirect orbits: {:?}",
-        system
-            .bodies
-            .values()
-            .fold(0, |memo, body| memo + body.num_orbits(&system))
+        system.total_orbits()?
     );
 
+    let you = system.body("YOU")?;
+    let san = system.body("SAN")?;
+
     println!(
         "Transfer distance: {}",
-        system.transfer_distance(
-            system.bodies.get("YOU").unwrap(),
-            system.bodies.get("SAN").unwrap(),
-        )
-        .expect("Failed to calculate YOU->SAN transfer distance.")
+        system
+            .transfer_distance(you, san)?
+            .expect("Failed to calculate YOU->SAN transfer distance.")
     );
+
+    let path = system
+        .transfer_path(you, san)?
+        .expect("Failed to calculate YOU->SAN transfer path.");
+
+    println!("Transfer path: {}", path.join(" -> "));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_system() -> System {
+        System::new_with_data(vec![
+            ("COM", "B"),
+            ("B", "C"),
+            ("C", "D"),
+            ("D", "E"),
+            ("E", "F"),
+            ("B", "G"),
+            ("G", "H"),
+            ("D", "I"),
+            ("E", "J"),
+            ("J", "K"),
+            ("K", "L"),
+        ])
+    }
+
+    #[test]
+    fn test_parse_orbits() {
+        let system = System::new_with_data(parse_orbits(
+            "COM)B
+             B)C
+             C)D
+             D)E
+             E)F
+             B)G
+             G)H
+             D)I
+             E)J
+             J)K
+             K)L",
+        ));
+
+        assert_eq!(system.bodies.len(), 12);
+        assert_eq!(system.total_orbits(), Ok(42));
+    }
+
+    #[test]
+    fn test_total_orbits() {
+        assert_eq!(example_system().total_orbits(), Ok(42));
+    }
+
+    #[test]
+    fn test_transfer_path() {
+        let system = System::new_with_data(vec![
+            ("COM", "B"),
+            ("B", "C"),
+            ("C", "D"),
+            ("D", "E"),
+            ("E", "F"),
+            ("B", "G"),
+            ("G", "H"),
+            ("D", "I"),
+            ("E", "J"),
+            ("J", "K"),
+            ("K", "L"),
+            ("K", "YOU"),
+            ("I", "SAN"),
+        ]);
+
+        let path = system
+            .transfer_path(&system.bodies["YOU"], &system.bodies["SAN"])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(path, vec!["K", "J", "E", "D", "I"]);
+
+        assert_eq!(
+            system.transfer_distance(&system.bodies["K"], &system.bodies["I"]),
+            Ok(Some(path.len() - 1))
+        );
+
+        assert_eq!(path.len() - 1, 4);
+
+        assert_eq!(
+            system.transfer_path(&system.bodies["COM"], &system.bodies["SAN"]),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let system = example_system();
+
+        let names: Vec<&str> = system
+            .ancestors("L")
+            .unwrap()
+            .iter()
+            .map(|body| body.name.as_str())
+            .collect();
+
+        assert_eq!(names, vec!["K", "J", "E", "D", "C", "B", "COM"]);
+
+        assert!(system.ancestors("COM").unwrap().is_empty());
+        assert_eq!(
+            system.ancestors("nope").err(),
+            Some(OrbitError::UnknownBody(String::from("nope")))
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let system = System::new_with_data(vec![("A", "B"), ("B", "A")]);
+        let cycle = Some(OrbitError::Cycle(vec![
+            String::from("A"),
+            String::from("B"),
+        ]));
+
+        assert_eq!(
+            system.bodies["A"]
+                .num_orbits(&system, &mut HashMap::new())
+                .err(),
+            cycle
+        );
+
+        assert_eq!(system.ancestors("A").err(), cycle);
+
+        assert!(matches!(
+            system.total_orbits(),
+            Err(OrbitError::Cycle(ref names)) if names.len() == 2
+        ));
+
+        assert!(matches!(
+            system.transfer_distance(&system.bodies["A"], &system.bodies["B"]),
+            Err(OrbitError::Cycle(_))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_body() {
+        let mut system = example_system();
+
+        system.bodies.insert(
+            String::from("X"),
+            Body::new_with_parent(String::from("X"), String::from("nope")),
+        );
+
+        let unknown = Some(OrbitError::UnknownBody(String::from("nope")));
+
+        assert_eq!(system.total_orbits().err(), unknown);
+        assert_eq!(system.ancestors("X").err(), unknown);
+        assert_eq!(system.body("nope").err(), unknown);
+
+        assert_eq!(
+            system
+                .transfer_distance(&system.bodies["X"], &system.bodies["L"])
+                .err(),
+            unknown
+        );
+    }
 }
@@ -1,40 +1,23 @@
 use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io;
 
 extern crate intcode;
 use intcode::{Program, ProgramState};
 
 extern crate permutohedron;
 
-/// Provided with a path to a file containing an intcode program, reads the file and returns a
-/// vector of the intcodes.
-fn read_intcodes(path: &str) -> Vec<i64> {
-    let file = File::open(path).unwrap();
-    let mut reader = BufReader::new(file);
-
-    let mut first_line = String::new();
-    reader.read_line(&mut first_line).unwrap();
-
-    first_line
-        .trim()
-        .split(",")
-        .map(|intcode| intcode.parse::<i64>().unwrap())
-        .collect()
-}
-
 /// Calculates the maximum signal which may be sent to the thrusters depending on the setting of
 /// each amplifier. Part one of day seven.
-fn non_feedback_amplifier_power(intcodes: &Vec<i64>, settings: Vec<i64>) -> i64 {
+fn non_feedback_amplifier_power(intcodes: &[i64], settings: Vec<i64>) -> i64 {
     let mut last_output = 0;
 
     for input in settings.iter() {
-        let mut amplifier = Program::new(intcodes.clone());
+        let mut amplifier = Program::new(intcodes.to_vec());
 
         amplifier.push_input(*input);
         amplifier.push_input(last_output);
 
-        match amplifier.run() {
+        match amplifier.run().unwrap() {
             ProgramState::Halt => panic!("Unexpected Halt without value in part 1"),
             ProgramState::Wait => panic!("No input available"),
             ProgramState::Output(value) => last_output = value,
@@ -48,12 +31,12 @@ fn non_feedback_amplifier_power(intcodes: &Vec<i64>, settings: Vec<i64>) -> i64
 /// each amplifier. In this case, the last amplifier is routed back to the first in a feedback loop.
 /// Each amplifier is contintually stopped when it produces output, and resumed when new input is
 /// available until all have halted.
-fn feedback_amplifier_power(intcodes: &Vec<i64>, settings: Vec<i64>) -> i64 {
+fn feedback_amplifier_power(intcodes: &[i64], settings: Vec<i64>) -> i64 {
     let mut amplifiers = VecDeque::new();
     let mut last_output = 0;
 
     for input in settings.iter() {
-        let mut amplifier = Program::new(intcodes.clone());
+        let mut amplifier = Program::new(intcodes.to_vec());
 
         // Provide initial phase setting.
         amplifier.push_input(*input);
@@ -63,7 +46,7 @@ fn feedback_amplifier_power(intcodes: &Vec<i64>, settings: Vec<i64>) -> i64 {
     while let Some(mut amplifier) = amplifiers.pop_front() {
         amplifier.push_input(last_output);
 
-        match amplifier.run() {
+        match amplifier.run().unwrap() {
             ProgramState::Output(value) => {
                 last_output = value;
 
@@ -77,48 +60,80 @@ fn feedback_amplifier_power(intcodes: &Vec<i64>, settings: Vec<i64>) -> i64 {
     last_output
 }
 
-fn part_one(intcodes: &Vec<i64>) -> i64 {
-    let mut max_output = 0;
+/// Tries every ordering of the `phases`, one per amplifier, returning the maximum signal which may
+/// be sent to the thrusters along with the ordering which produced it. When `feedback` is true the
+/// amplifiers are run in a feedback loop. With no phases the signal is 0 and the ordering empty.
+fn best_phases(intcodes: &[i64], phases: &[i64], feedback: bool) -> (i64, Vec<i64>) {
+    let mut best: Option<(i64, Vec<i64>)> = None;
 
-    let mut inputs = vec![0, 1, 2, 3, 4];
+    let mut inputs = phases.to_vec();
     let heap = permutohedron::Heap::new(&mut inputs);
 
     for permutation in heap {
-        let last_output = non_feedback_amplifier_power(&intcodes, permutation);
-
-        if last_output > max_output {
-            max_output = last_output;
+        let last_output = if feedback {
+            feedback_amplifier_power(intcodes, permutation.clone())
+        } else {
+            non_feedback_amplifier_power(intcodes, permutation.clone())
+        };
+
+        let is_better = match &best {
+            Some((max_output, _)) => last_output > *max_output,
+            None => true,
+        };
+
+        if is_better {
+            best = Some((last_output, permutation));
         }
     }
 
-    max_output
+    best.unwrap_or((0, Vec::new()))
+}
+
+/// Tries every ordering of the `phases`, one per amplifier, returning the maximum signal which may
+/// be sent to the thrusters. When `feedback` is true the amplifiers are run in a feedback loop.
+///
+/// `main` reports the winning phases too, so this and the parts built on it are only used by
+/// callers -- such as the tests -- which need just the signal.
+#[allow(dead_code)]
+fn max_thruster_signal(intcodes: &[i64], phases: &[i64], feedback: bool) -> i64 {
+    best_phases(intcodes, phases, feedback).0
+}
+
+#[allow(dead_code)]
+fn part_one(intcodes: &[i64]) -> i64 {
+    max_thruster_signal(intcodes, &[0, 1, 2, 3, 4], false)
+}
+
+/// Returns the part one signal along with the phase ordering which produced it.
+fn part_one_with_phases(intcodes: &[i64]) -> (i64, Vec<i64>) {
+    best_phases(intcodes, &[0, 1, 2, 3, 4], false)
 }
 
 /// This is similar to part one, except that rather than iterating through each amplifier once, we
 /// need to keep iterating until the last amplifier halts constantly feeding the output from an
 /// amplifier into the next.
-fn part_two(intcodes: &Vec<i64>) -> i64 {
-    let mut max_output = 0;
+#[allow(dead_code)]
+fn part_two(intcodes: &[i64]) -> i64 {
+    max_thruster_signal(intcodes, &[5, 6, 7, 8, 9], true)
+}
 
-    let mut inputs = vec![5, 6, 7, 8, 9];
-    let heap = permutohedron::Heap::new(&mut inputs);
+/// Returns the part two signal along with the phase ordering which produced it.
+fn part_two_with_phases(intcodes: &[i64]) -> (i64, Vec<i64>) {
+    best_phases(intcodes, &[5, 6, 7, 8, 9], true)
+}
 
-    for permutation in heap {
-        let last_output = feedback_amplifier_power(&intcodes, permutation);
+fn main() -> Result<(), io::Error> {
+    let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
 
-        if last_output > max_output {
-            max_output = last_output;
-        }
-    }
-
-    max_output
-}
+    let (signal, phases) = part_one_with_phases(&intcodes);
+    println!("Part 1: {}", signal);
+    println!("  phases: {:?}", phases);
 
-fn main() {
-    let intcodes = read_intcodes("data/intcodes.txt");
+    let (signal, phases) = part_two_with_phases(&intcodes);
+    println!("Part 2: {}", signal);
+    println!("  phases: {:?}", phases);
 
-    println!("Part 1: {}", part_one(&intcodes));
-    println!("Part 2: {}", part_two(&intcodes));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -180,4 +195,69 @@ mod tests {
             18216
         );
     }
+
+    #[test]
+    fn test_max_thruster_signal() {
+        let intcodes = vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+
+        assert_eq!(part_one(&intcodes), 43210);
+
+        // Three amplifiers.
+        assert_eq!(max_thruster_signal(&intcodes, &[0, 1, 2], false), 210);
+
+        let intcodes = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+
+        assert_eq!(part_two(&intcodes), 139629729);
+    }
+
+    #[test]
+    fn test_best_phases() {
+        let intcodes = vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+
+        assert_eq!(
+            part_one_with_phases(&intcodes),
+            (43210, vec![4, 3, 2, 1, 0])
+        );
+
+        // No amplifiers.
+        assert_eq!(best_phases(&intcodes, &[], false), (0, vec![]));
+
+        // Outputs twice its input, less the phase setting and one, so every signal is negative.
+        let intcodes = vec![
+            3, 30, 3, 31, 1002, 31, 2, 31, 1002, 30, -1, 30, 1, 31, 30, 31, 1001, 31, -1, 31, 4,
+            31, 99,
+        ];
+
+        assert_eq!(
+            best_phases(&intcodes, &[0, 1, 2], false),
+            (-11, vec![0, 1, 2])
+        );
+
+        let intcodes = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+
+        assert_eq!(
+            part_two_with_phases(&intcodes),
+            (139629729, vec![9, 8, 7, 6, 5])
+        );
+    }
+
+    #[test]
+    fn test_real_input() -> Result<(), io::Error> {
+        let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
+
+        assert_eq!(part_one(&intcodes), 18812);
+        assert_eq!(part_two(&intcodes), 25534964);
+
+        Ok(())
+    }
 }
@@ -1,19 +1,10 @@
+use std::env;
 use std::fs;
+use std::io;
 
-/// Given a layer, multiplies the number of ones and the number of twos.
-fn ones_times_twos(layer: &Vec<u8>) -> i64 {
-    let mut ones = 0;
-    let mut twos = 0;
-
-    for pixel in layer {
-        match *pixel {
-            1u8 => ones += 1,
-            2u8 => twos += 1,
-            _ => {}
-        }
-    }
-
-    ones * twos
+/// Counts how many pixels in the layer have the given digit.
+fn count_digit(layer: &[u8], digit: u8) -> usize {
+    layer.iter().filter(|pixel| **pixel == digit).count()
 }
 
 /// Composes the layers of the image, from the top-most layer to the bottom, into a final image.
@@ -35,6 +26,34 @@ fn compose_image_from_layers(pixels: &Vec<u8>, pixels_per_layer: usize) -> Vec<u
     image
 }
 
+/// Writes a composed image to the file at the given `path` as a binary (P6) PPM, so that it may be
+/// opened in an image viewer. Pixels with a value of 1 are white, and all others are black.
+///
+/// Returns an InvalidInput error if the image doesn't contain exactly `width * height` pixels.
+fn write_ppm(image: &[u8], width: usize, height: usize, path: &str) -> io::Result<()> {
+    if image.len() != width * height {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Expected {} pixels for a {}x{} image, got {}",
+                width * height,
+                width,
+                height,
+                image.len()
+            ),
+        ));
+    }
+
+    let mut data = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+
+    for pixel in image {
+        let value = if *pixel == 1 { 255 } else { 0 };
+        data.extend_from_slice(&[value, value, value]);
+    }
+
+    fs::write(path, data)
+}
+
 /// Reads pixel data from the file at the given `path`.
 fn read_data(path: &str) -> Result<Vec<u8>, std::io::Error> {
     let raw_content = fs::read_to_string(path)?;
@@ -49,20 +68,22 @@ fn read_data(path: &str) -> Result<Vec<u8>, std::io::Error> {
     Ok(data)
 }
 
-/// Receives pixel data and the number of pixels per layer, finds the layer with the least zeros and
-/// multiplies the number of ones by twos in that layer. Returns None if the pixel data is empty.
-fn part_one(pixels: &Vec<u8>, pixels_per_layer: usize) -> Option<i64> {
+/// Receives pixel data and the dimensions of the image, finds the layer with the fewest of the
+/// `fewest` digit and multiplies the counts of the two `factors` digits in that layer. Returns None
+/// if the pixel data is empty.
+fn part_one(
+    pixels: &Vec<u8>,
+    width: usize,
+    height: usize,
+    fewest: u8,
+    factors: (u8, u8),
+) -> Option<usize> {
+    let pixels_per_layer = width * height;
     let layers = pixels.chunks(pixels_per_layer);
 
-    let least_zeros = layers.min_by_key(|layer| {
-        let zeros: Vec<&u8> = layer.iter().filter(|pixel| **pixel == 0u8).collect();
-        zeros.len()
-    });
+    let least_fewest = layers.min_by_key(|layer| count_digit(layer, fewest));
 
-    match least_zeros {
-        Some(layer) => Some(ones_times_twos(&layer.to_vec())),
-        None => None,
-    }
+    least_fewest.map(|layer| count_digit(layer, factors.0) * count_digit(layer, factors.1))
 }
 
 /// Composes the individual layers of an image, by overlaying the top-most layer over the layer
@@ -72,12 +93,12 @@ fn part_one(pixels: &Vec<u8>, pixels_per_layer: usize) -> Option<i64> {
 ///
 /// Returns the final "image" as a string where each white character is an "o" and each black
 /// character is left as whitespace.
-fn part_two(pixels: &Vec<u8>, pixels_per_row: usize, pixels_per_layer: usize) -> String {
+fn part_two(pixels: &Vec<u8>, width: usize, height: usize) -> String {
+    let pixels_per_layer = width * height;
     let image = compose_image_from_layers(pixels, pixels_per_layer);
-    let rows = image.chunks(pixels_per_row);
+    let rows = image.chunks(width);
 
-    let mut rendered =
-        String::with_capacity(pixels_per_layer * 2 + pixels_per_layer / pixels_per_row + 1);
+    let mut rendered = String::with_capacity(pixels_per_layer * 2 + height + 1);
 
     for row in rows {
         for pixel in row {
@@ -97,15 +118,21 @@ fn part_two(pixels: &Vec<u8>, pixels_per_row: usize, pixels_per_layer: usize) ->
 
 fn main() -> Result<(), std::io::Error> {
     let pixels = read_data("data/image.txt")?;
-    let pixels_per_layer = 25 * 6;
 
-    match part_one(&pixels, pixels_per_layer) {
+    match part_one(&pixels, 25, 6, 0, (1, 2)) {
         Some(result) => println!("Part one: {}", result),
         None => println!("Part one: No matching layer found."),
     };
 
     println!("Part two:");
-    println!("{}", part_two(&pixels, 25, pixels_per_layer));
+    println!("{}", part_two(&pixels, 25, 6));
+
+    // With the --ppm flag the composed image is also written to image.ppm.
+    if env::args().skip(1).any(|arg| arg == "--ppm") {
+        let image = compose_image_from_layers(&pixels, 25 * 6);
+        write_ppm(&image, 25, 6, "image.ppm")?;
+        println!("Image written to image.ppm");
+    }
 
     Ok(())
 }
@@ -114,6 +141,19 @@ fn main() -> Result<(), std::io::Error> {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_part_one_example() {
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2];
+
+        assert_eq!(part_one(&data, 3, 2, 0, (1, 2)), Some(1));
+    }
+
+    #[test]
+    fn test_count_digit() {
+        assert_eq!(count_digit(&[1, 1, 2, 0], 1), 2);
+        assert_eq!(count_digit(&[1, 1, 2, 0], 3), 0);
+    }
+
     #[test]
     fn test_part_two_example() {
         let data = vec![0, 2, 2, 2, 1, 1, 2, 2, 2, 2, 1, 2, 0, 0, 0, 0];
@@ -121,4 +161,37 @@ mod test {
 
         assert_eq!(image, vec![0, 1, 1, 0]);
     }
+
+    #[test]
+    fn test_part_two_render_example() {
+        let data = vec![0, 2, 2, 2, 1, 1, 2, 2, 2, 2, 1, 2, 0, 0, 0, 0];
+
+        assert_eq!(part_two(&data, 2, 2), "  o \no   \n");
+    }
+
+    #[test]
+    fn test_write_ppm() -> Result<(), std::io::Error> {
+        let path = std::env::temp_dir().join("day-eight-test.ppm");
+        let path = path.to_str().unwrap();
+
+        write_ppm(&[0, 1, 1, 0], 2, 2, path)?;
+
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend_from_slice(&[0, 0, 0, 255, 255, 255, 255, 255, 255, 0, 0, 0]);
+
+        assert_eq!(fs::read(path)?, expected);
+
+        fs::remove_file(path)
+    }
+
+    #[test]
+    fn test_write_ppm_wrong_size() {
+        let path = std::env::temp_dir().join("day-eight-test-wrong-size.ppm");
+        let path = path.to_str().unwrap();
+
+        let error = write_ppm(&[0, 1, 1], 2, 2, path).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(fs::metadata(path).is_err());
+    }
 }
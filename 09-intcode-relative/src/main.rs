@@ -1,49 +1,35 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io;
 
 extern crate intcode;
-use intcode::{Program, ProgramState};
-
-/// Provided with a path to a file containing an intcode program, reads the file and returns a
-/// vector of the intcodes.
-fn read_intcodes(path: &str) -> Vec<i64> {
-    let file = File::open(path).unwrap();
-    let mut reader = BufReader::new(file);
-
-    let mut first_line = String::new();
-    reader.read_line(&mut first_line).unwrap();
-
-    first_line
-        .trim()
-        .split(",")
-        .map(|intcode| intcode.parse::<i64>().unwrap())
-        .collect()
-}
+use intcode::Program;
 
 /// Runs the BOOST program in self-test mode (input = 1).
 fn part_one(intcodes: &Vec<i64>) -> Vec<i64> {
     let mut program = Program::new(intcodes.clone());
     program.push_input(1);
-    program.run_capturing_output()
+    program.outputs().collect()
 }
 
 /// Runs the BOOST program in sensor boost mode (input = 2).
 fn part_two(intcodes: &Vec<i64>) -> Vec<i64> {
     let mut program = Program::new(intcodes.clone());
     program.push_input(2);
-    program.run_capturing_output()
+    program.outputs().collect()
 }
 
-fn main() {
-    let intcodes = read_intcodes("data/intcodes.txt");
+fn main() -> Result<(), io::Error> {
+    let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
 
     println!("Part one: {:?}", part_one(&intcodes));
     println!("Part two: {:?}", part_two(&intcodes));
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use intcode::ProgramState;
 
     #[test]
     fn test_day_9() {
@@ -54,13 +40,13 @@ mod tests {
 
         let mut program = Program::new(intcodes.clone());
 
-        assert_eq!(program.run_capturing_output(), intcodes);
+        assert_eq!(program.run_capturing_output().unwrap(), intcodes);
 
         // Program outputs a 16-digit number.
         let intcodes = vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0];
 
         let mut program = Program::new(intcodes);
-        let result = program.run();
+        let result = program.run().unwrap();
 
         assert_eq!(result, ProgramState::Output(1219070632396864));
 
@@ -68,7 +54,7 @@ mod tests {
         let intcodes = vec![104, 1125899906842624, 99];
 
         let mut program = Program::new(intcodes);
-        let result = program.run();
+        let result = program.run().unwrap();
 
         assert_eq!(result, ProgramState::Output(1125899906842624));
     }
@@ -1,6 +1,6 @@
 /// Oh god, don't look at it!
 use std::cmp::Ordering;
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::fs;
 use std::iter::FromIterator;
 
@@ -16,10 +16,23 @@ impl Point {
         ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
     }
 
+    /// Returns the direction from this point to the `other` as the smallest integer (x, y) step
+    /// which lands on it. Points in the same line of sight share the same direction.
+    fn direction(&self, other: &Point) -> (i64, i64) {
+        let x_distance = (other.x - self.x) as i64;
+        let y_distance = (other.y - self.y) as i64;
+        let divisor = gcd(x_distance.abs(), y_distance.abs());
+
+        (x_distance / divisor, y_distance / divisor)
+    }
+
+    /// Returns the clockwise angle in degrees from this point to the `other`, where straight up is
+    /// 0, right is 90, down is 180, and left is 270.
     fn angle(&self, other: &Point) -> f64 {
-        let x_distance = self.x - other.x;
+        let x_distance = other.x - self.x;
+        // The y axis increases downwards, so is flipped for up to be positive.
         let y_distance = self.y - other.y;
-        let angle = x_distance.atan2(y_distance) * 180.0 / std::f64::consts::PI;
+        let angle = x_distance.atan2(y_distance).to_degrees();
 
         if angle < 0.0 {
             angle + 360.0
@@ -29,6 +42,19 @@ impl Point {
     }
 }
 
+fn gcd(a: i64, b: i64) -> i64 {
+    let mut a = a;
+    let mut b = b;
+
+    while b > 0 {
+        let m = a % b;
+        a = b;
+        b = m;
+    }
+
+    a
+}
+
 /// Stores an target point, and memoizes both the angle and distance from an origin to the target.
 struct Ray<'a> {
     target: &'a Point,
@@ -38,15 +64,10 @@ struct Ray<'a> {
 
 impl<'a> Ray<'a> {
     fn new(origin: &'a Point, target: &'a Point) -> Ray<'a> {
-        let angle = origin.angle(&target);
-
         Ray {
             target,
-            // I have to sort by negative angle, ensuring that those directly north come first
-            // (hence -360). I'm not entirely sure why; probably messed something up in
-            // Point::angle?
-            angle: if angle == 0.0 { -360.0 } else { -angle },
-            distance: origin.distance(&target),
+            angle: origin.angle(target),
+            distance: origin.distance(target),
         }
     }
 }
@@ -72,7 +93,7 @@ fn build_map(data: &str) -> Vec<Point> {
 
 /// Given a list of asteroid positions, and an origin asteroid, calculates the angle from the origin
 /// to all the asteroids (except the origin) in the list.
-fn visible_from_location<'a>(asteroids: &'a Vec<Point>, origin: &'a Point) -> Vec<Ray<'a>> {
+fn visible_from_location<'a>(asteroids: &'a [Point], origin: &'a Point) -> Vec<Ray<'a>> {
     asteroids
         .iter()
         .filter(|asteroid| *asteroid != origin)
@@ -80,24 +101,31 @@ fn visible_from_location<'a>(asteroids: &'a Vec<Point>, origin: &'a Point) -> Ve
         .collect::<Vec<Ray>>()
 }
 
-fn part_one<'a>(asteroids: &'a Vec<Point>) -> (&'a Point, usize) {
+/// Returns each asteroid along with the number of other asteroids which are visible from it.
+fn visibility_counts(asteroids: &[Point]) -> Vec<(&Point, usize)> {
+    asteroids
+        .iter()
+        .map(|asteroid| {
+            // Asteroids in the same direction hide those behind them, so only one is visible for
+            // each.
+            let directions: HashSet<(i64, i64)> = asteroids
+                .iter()
+                .filter(|other| *other != asteroid)
+                .map(|other| asteroid.direction(other))
+                .collect();
+
+            (asteroid, directions.len())
+        })
+        .collect()
+}
+
+fn part_one(asteroids: &[Point]) -> (&Point, usize) {
     let mut max = 0;
     let mut best = &asteroids[0];
 
-    for asteroid in asteroids {
-        let mut angles = visible_from_location(&asteroids, &asteroid);
-
-        // Have to sort in order for dedup_by_key to remove all duplicates.
-        angles.sort_by(|left, right| {
-            left.angle
-                .partial_cmp(&right.angle)
-                .unwrap_or(Ordering::Equal)
-        });
-
-        angles.dedup_by_key(|angle| angle.angle);
-
-        if angles.len() > max {
-            max = angles.len();
+    for (asteroid, visible) in visibility_counts(asteroids) {
+        if visible > max {
+            max = visible;
             best = asteroid;
         }
     }
@@ -105,7 +133,10 @@ fn part_one<'a>(asteroids: &'a Vec<Point>) -> (&'a Point, usize) {
     (best, max)
 }
 
-fn part_two(asteroids: &Vec<Point>, station: &Point, bet: usize) -> Option<f64> {
+/// Returns every asteroid in the order in which they are vaporized by a laser at the `station`,
+/// which starts pointing up and rotates clockwise, vaporizing only the closest asteroid at each
+/// angle on each rotation.
+fn vaporization_order<'a>(asteroids: &'a [Point], station: &'a Point) -> Vec<&'a Point> {
     let mut angles = visible_from_location(asteroids, station);
 
     // Sort first by distance...
@@ -123,30 +154,33 @@ fn part_two(asteroids: &Vec<Point>, station: &Point, bet: usize) -> Option<f64>
             .unwrap_or(Ordering::Equal)
     });
 
-    let mut angles = VecDeque::from_iter(&angles);
-    let mut count = 0;
+    let mut order = Vec::with_capacity(angles.len());
+    let mut angles = VecDeque::from_iter(angles);
 
     while let Some(asteroid) = angles.pop_front() {
         // The first asteroid popped off is always a new angle.
-        count += 1;
+        order.push(asteroid.target);
 
-        if count == bet {
-            return Some(asteroid.target.x * 100.0 + asteroid.target.y);
-        }
+        // Rotate any other asteroids with the same angle to the back of the queue, so that they are
+        // vaporized on a later rotation.
+        let hidden = angles
+            .iter()
+            .take_while(|next| next.angle == asteroid.angle)
+            .count();
 
-        while let Some(next) = angles.front() {
-            if next.angle == asteroid.angle {
-                // Rotate any other asteroids with the same angle to the back of the queue. This
-                // would be better to find the index of the first entry with a different angle, and
-                // rotate all at once.
-                angles.rotate_left(1);
-            } else {
-                break;
-            }
-        }
+        angles.rotate_left(hidden);
     }
 
-    None
+    order
+}
+
+/// Finds the asteroid which is vaporized `bet`th by the laser at the `station`, counting from one.
+/// Returns None if fewer asteroids than that are vaporized.
+fn part_two<'a>(asteroids: &'a [Point], station: &'a Point, bet: usize) -> Option<&'a Point> {
+    let order = vaporization_order(asteroids, station);
+
+    bet.checked_sub(1)
+        .and_then(|index| order.get(index).copied())
 }
 
 fn main() -> Result<(), std::io::Error> {
@@ -158,7 +192,10 @@ fn main() -> Result<(), std::io::Error> {
     let (station, asteroids_visible) = part_one(&map);
 
     println!("Part one: {:?}", asteroids_visible);
-    println!("Part two: {:?}", part_two(&map, station, 200));
+    println!(
+        "Part two: {:?}",
+        part_two(&map, station, 200).map(|asteroid| asteroid.x * 100.0 + asteroid.y)
+    );
 
     Ok(())
 }
@@ -172,6 +209,108 @@ mod test {
         lines.join("\n")
     }
 
+    fn large_example() -> Vec<Point> {
+        build_map(&trim_leading_whitespace(
+            ".#..##.###...#######
+             ##.############..##.
+             .#.######.########.#
+             .###.#######.####.#.
+             #####.##.#.##.###.##
+             ..#####..#.#########
+             ####################
+             #.####....###.#.#.##
+             ##.#################
+             #####.##.###..####..
+             ..######..##.#######
+             ####.##.####...##..#
+             .#####..#.######.###
+             ##...#.##########...
+             #.##########.#######
+             .####.#.###.###.#.##
+             ....##.##.###..#####
+             .#.#.###########.###
+             #.#.#.#####.####.###
+             ###.##.####.##.#..##",
+        ))
+    }
+
+    #[test]
+    fn test_angle() {
+        let origin = Point { x: 5.0, y: 5.0 };
+
+        assert_eq!(origin.angle(&Point { x: 5.0, y: 1.0 }), 0.0);
+        assert_eq!(origin.angle(&Point { x: 6.0, y: 4.0 }), 45.0);
+        assert_eq!(origin.angle(&Point { x: 9.0, y: 5.0 }), 90.0);
+        assert_eq!(origin.angle(&Point { x: 5.0, y: 9.0 }), 180.0);
+        assert_eq!(origin.angle(&Point { x: 1.0, y: 5.0 }), 270.0);
+        assert_eq!(origin.angle(&Point { x: 4.0, y: 4.0 }), 315.0);
+    }
+
+    #[test]
+    fn test_direction() {
+        let origin = Point { x: 11.0, y: 13.0 };
+
+        assert_eq!(origin.direction(&Point { x: 14.0, y: 19.0 }), (1, 2));
+        assert_eq!(origin.direction(&Point { x: 17.0, y: 25.0 }), (1, 2));
+        assert_eq!(origin.direction(&Point { x: 5.0, y: 13.0 }), (-1, 0));
+        assert_eq!(origin.direction(&Point { x: 11.0, y: 1.0 }), (0, -1));
+        assert_eq!(origin.direction(&Point { x: 2.0, y: 7.0 }), (-3, -2));
+    }
+
+    #[test]
+    fn test_part_one_large_example_is_stable() {
+        let map = large_example();
+
+        for _ in 0..3 {
+            let (station, visible) = part_one(&map);
+
+            assert_eq!(visible, 210);
+            assert_eq!(station, &Point { x: 11.0, y: 13.0 });
+        }
+    }
+
+    #[test]
+    fn test_vaporization_order() {
+        let map = large_example();
+        let order = vaporization_order(&map, &Point { x: 11.0, y: 13.0 });
+
+        assert_eq!(order.len(), map.len() - 1);
+
+        assert_eq!(order[0], &Point { x: 11.0, y: 12.0 });
+        assert_eq!(order[1], &Point { x: 12.0, y: 1.0 });
+        assert_eq!(order[2], &Point { x: 12.0, y: 2.0 });
+        assert_eq!(order[9], &Point { x: 12.0, y: 8.0 });
+        assert_eq!(order[19], &Point { x: 16.0, y: 0.0 });
+        assert_eq!(order[49], &Point { x: 16.0, y: 9.0 });
+        assert_eq!(order[99], &Point { x: 10.0, y: 16.0 });
+        assert_eq!(order[198], &Point { x: 9.0, y: 6.0 });
+        assert_eq!(order[199], &Point { x: 8.0, y: 2.0 });
+        assert_eq!(order[200], &Point { x: 10.0, y: 9.0 });
+        assert_eq!(order[298], &Point { x: 11.0, y: 1.0 });
+    }
+
+    #[test]
+    fn test_visibility_counts() {
+        let map = build_map(&trim_leading_whitespace(
+            ".#..#
+             .....
+             #####
+             ....#
+             ...##",
+        ));
+
+        let counts = visibility_counts(&map);
+
+        assert_eq!(counts.len(), map.len());
+        assert_eq!(counts[0], (&Point { x: 1.0, y: 0.0 }, 7));
+        assert_eq!(counts[4], (&Point { x: 2.0, y: 2.0 }, 7));
+        assert_eq!(counts[6], (&Point { x: 4.0, y: 2.0 }, 5));
+
+        let best = counts.iter().max_by_key(|(_, visible)| *visible).unwrap();
+
+        assert_eq!(best, &(&Point { x: 3.0, y: 4.0 }, 8));
+    }
+
     #[test]
     fn test_part_one() {
         let map = trim_leading_whitespace(
@@ -263,32 +402,22 @@ mod test {
 
     #[test]
     fn test_part_two() {
-        let map = trim_leading_whitespace(
-            ".#..##.###...#######
-             ##.############..##.
-             .#.######.########.#
-             .###.#######.####.#.
-             #####.##.#.##.###.##
-             ..#####..#.#########
-             ####################
-             #.####....###.#.#.##
-             ##.#################
-             #####.##.###..####..
-             ..######..##.#######
-             ####.##.####...##..#
-             .#####..#.######.###
-             ##...#.##########...
-             #.##########.#######
-             .####.#.###.###.#.##
-             ....##.##.###..#####
-             .#.#.###########.###
-             #.#.#.#####.####.###
-             ###.##.####.##.#..##",
-        );
-
-        let map = build_map(&map);
+        let map = large_example();
         let answer = part_two(&map, &Point { x: 11.0, y: 13.0 }, 200);
 
-        assert_eq!(answer, Some(802.0));
+        assert_eq!(answer, Some(&Point { x: 8.0, y: 2.0 }));
+    }
+
+    #[test]
+    fn test_part_two_bet_out_of_range() {
+        let map = large_example();
+        let station = Point { x: 11.0, y: 13.0 };
+
+        assert_eq!(
+            part_two(&map, &station, 299),
+            Some(&Point { x: 11.0, y: 1.0 })
+        );
+        assert_eq!(part_two(&map, &station, 300), None);
+        assert_eq!(part_two(&map, &station, 0), None);
     }
 }
@@ -1,7 +1,6 @@
 use std::collections::HashMap;
 use std::fmt;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io;
 
 extern crate intcode;
 use intcode::{Program, ProgramState};
@@ -46,64 +45,92 @@ impl Direction {
     }
 }
 
-/// Provided with a path to a file containing an intcode program, reads the file and returns a
-/// vector of the intcodes.
-fn read_intcodes(path: &str) -> Vec<i64> {
-    let file = File::open(path).unwrap();
-    let mut reader = BufReader::new(file);
+/// Contains the pixels visited by a robot, and the color painted in each. The first hash map
+/// contains keys of coordinates (x, y), and the color painted (0 for black, 1 for white). The
+/// second contains the number of times each of those coordinates was painted.
+struct Canvas(HashMap<(i64, i64), usize>, HashMap<(i64, i64), usize>);
 
-    let mut first_line = String::new();
-    reader.read_line(&mut first_line).unwrap();
+impl Canvas {
+    fn new() -> Canvas {
+        Canvas(HashMap::new(), HashMap::new())
+    }
 
-    first_line
-        .trim()
-        .split(",")
-        .map(|intcode| intcode.parse::<i64>().unwrap())
-        .collect()
-}
+    /// Paints the panel at `position` with the given color.
+    fn paint(&mut self, position: (i64, i64), color: usize) {
+        self.0.insert(position, color);
+        *self.1.entry(position).or_insert(0) += 1;
+    }
 
-/// Contains the pixels visited by a robot, and the color painted in each. The internal hash map
-/// contains keys of coordinates (x, y), and the color painted (0 for black, 1 for white).
-struct Canvas(HashMap<(i64, i64), usize>);
+    /// Returns the number of panels painted one or more times, regardless of whether the robot
+    /// painted them the color they already were.
+    fn painted_count(&self) -> usize {
+        self.1.len()
+    }
 
-impl Canvas {
-    fn new() -> Canvas {
-        Canvas(HashMap::new())
+    /// Returns the number of panels which were painted more than once.
+    fn painted_at_least_twice(&self) -> usize {
+        self.1.values().filter(|&&count| count >= 2).count()
     }
-}
 
-impl fmt::Display for Canvas {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    /// Returns the painted region as a matrix of rows, trimmed to the bounding box of the panels
+    /// painted by the robot. Each cell is true when white, and false when black or unpainted.
+    fn to_grid(&self) -> Vec<Vec<bool>> {
+        if self.0.is_empty() {
+            return Vec::new();
+        }
+
         let min_x = self.0.keys().min_by_key(|(x, _)| x).unwrap().0;
         let max_x = self.0.keys().max_by_key(|(x, _)| x).unwrap().0;
         let min_y = self.0.keys().min_by_key(|(_, y)| y).unwrap().1;
         let max_y = self.0.keys().max_by_key(|(_, y)| y).unwrap().1;
 
-        let width = (max_x + 1) - min_x;
-        let height = (max_y + 1) - min_y;
+        (min_y..(max_y + 1))
+            .map(|y| {
+                (min_x..(max_x + 1))
+                    .map(|x| match self.0.get(&(x, y)) {
+                        Some(color) => *color != 0,
+                        None => false,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
 
-        // Two characters per pixel, plus a newline per row.
-        let mut output = String::with_capacity(((2 * width) * height + height) as usize);
+    /// Renders the painted region using the `on` character for white panels, and `off` for black
+    /// or unpainted panels. Each pixel is followed by a space, and each row by a newline.
+    fn render_with(&self, on: char, off: char) -> String {
+        let grid = self.to_grid();
+        let width = grid.first().map_or(0, |row| row.len());
 
-        for y in min_y..(max_y + 1) {
-            for x in min_x..(max_x + 1) {
-                match self.0.get(&(x, y)) {
-                    Some(color) if *color != 0 => {
-                        output.push('#');
-                    }
-                    _ => output.push(' '),
-                }
+        // Two characters per pixel, plus a newline per row.
+        let mut output = String::with_capacity((2 * width) * grid.len() + grid.len());
 
+        for row in grid {
+            for white in row {
+                output.push(if white { on } else { off });
                 output.push(' ');
             }
 
             output.push('\n');
         }
 
-        write!(f, "{}", output)
+        output
+    }
+}
+
+impl fmt::Display for Canvas {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render_with('#', ' '))
     }
 }
 
+/// Describes why a painter robot was unable to finish painting.
+#[derive(Debug, PartialEq)]
+enum PaintError {
+    /// The program halted after sending a color without the direction in which to turn.
+    DanglingOutput(i64),
+}
+
 struct PainterRobot {
     program: Program,
 }
@@ -114,7 +141,10 @@ impl PainterRobot {
     }
 
     /// Runs the painter robot.
-    fn paint(mut self, initial_color: usize) -> Canvas {
+    ///
+    /// Returns an error if the program halts part-way through an instruction; that is, when it has
+    /// output a color but not the direction in which the robot should then turn.
+    fn paint(mut self, initial_color: usize) -> Result<Canvas, PaintError> {
         let mut position = (0, 0);
         let mut direction = Direction::Up;
         let mut prev_output = None;
@@ -129,14 +159,14 @@ impl PainterRobot {
         // painted (0 is black, 1 is white), and the second is the direction it will turn (0 is
         // left, 1 is right).
         loop {
-            match self.program.run() {
+            match self.program.run().unwrap() {
                 ProgramState::Output(value) => {
                     // Robot has moved. Collect the values
                     match prev_output {
                         None => prev_output = Some(value),
                         Some(color) => {
                             // We have two values. The robot is ready to move.
-                            canvas.0.insert(position, color as usize);
+                            canvas.paint(position, color as usize);
 
                             // Set the new direction and position of the robot.
                             direction = direction.turn(value == 0);
@@ -157,24 +187,34 @@ impl PainterRobot {
             }
         }
 
-        canvas
+        match prev_output {
+            Some(color) => Err(PaintError::DanglingOutput(color)),
+            None => Ok(canvas),
+        }
     }
 }
 
-fn main() {
-    let intcodes = read_intcodes("data/intcodes.txt");
+fn main() -> Result<(), io::Error> {
+    let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
+
+    let canvas = PainterRobot::new(Program::new(intcodes.clone()))
+        .paint(0)
+        .unwrap();
 
     println!(
-        "Part one: {:?}",
-        PainterRobot::new(Program::new(intcodes.clone()))
-            .paint(0)
-            .0
-            .len()
+        "Part one: {:?} ({} painted more than once)",
+        canvas.painted_count(),
+        canvas.painted_at_least_twice()
     );
 
     println!("Part two:");
 
-    println!("{}", PainterRobot::new(Program::new(intcodes)).paint(1));
+    println!(
+        "{}",
+        PainterRobot::new(Program::new(intcodes)).paint(1).unwrap()
+    );
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -182,18 +222,23 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_part_one() {
-        let intcodes = read_intcodes("data/intcodes.txt");
-        let touched = PainterRobot::new(Program::new(intcodes)).paint(0).0.len();
+    fn test_part_one() -> Result<(), io::Error> {
+        let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
+        let touched = PainterRobot::new(Program::new(intcodes))
+            .paint(0)
+            .unwrap()
+            .painted_count();
 
         assert_eq!(touched, 2088);
+
+        Ok(())
     }
 
     #[test]
-    fn test_part_two() {
-        let intcodes = read_intcodes("data/intcodes.txt");
+    fn test_part_two() -> Result<(), io::Error> {
+        let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
 
-        let canvas = PainterRobot::new(Program::new(intcodes)).paint(1);
+        let canvas = PainterRobot::new(Program::new(intcodes)).paint(1).unwrap();
         let printed = format!("{}", canvas);
 
         assert_eq!(
@@ -207,5 +252,54 @@ mod tests {
                 "    # #     #     #     # #     #     #   #         # # # #     # #     #             \n",
             )
         );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_grid() {
+        let mut canvas = Canvas::new();
+
+        canvas.0.insert((-1, 2), 1);
+        canvas.0.insert((0, 2), 0);
+        canvas.0.insert((1, 3), 1);
+
+        assert_eq!(
+            canvas.to_grid(),
+            vec![vec![true, false, false], vec![false, false, true]]
+        );
+    }
+
+    #[test]
+    fn test_render_with() {
+        let mut canvas = Canvas::new();
+
+        canvas.paint((0, 0), 1);
+        canvas.paint((0, 1), 0);
+
+        assert_eq!(canvas.render_with('*', '.'), "* \n. \n");
+    }
+
+    #[test]
+    fn test_painted_counts() {
+        // Paints white and turns left four times, returning to and repainting the first panel.
+        let mut intcodes = [104, 1, 104, 0].repeat(5);
+        intcodes.push(99);
+
+        let canvas = PainterRobot::new(Program::new(intcodes)).paint(0).unwrap();
+
+        assert_eq!(canvas.painted_count(), 4);
+        assert_eq!(canvas.painted_at_least_twice(), 1);
+    }
+
+    #[test]
+    fn test_dangling_output() {
+        // Paints white and turns left, then paints black and halts without turning.
+        let program = Program::new(vec![104, 1, 104, 0, 104, 0, 99]);
+
+        assert_eq!(
+            PainterRobot::new(program).paint(0).err(),
+            Some(PaintError::DanglingOutput(0))
+        );
     }
 }
@@ -1,7 +1,9 @@
 use std::collections::HashSet;
-use std::ops::Index;
+use std::fs;
+use std::io;
+use std::ops::{Add, AddAssign, Index, Sub};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 struct Position {
     x: i64,
     y: i64,
@@ -21,13 +23,32 @@ impl Index<usize> for Position {
     }
 }
 
+impl Add for Position {
+    type Output = Position;
+
+    fn add(self, other: Position) -> Position {
+        Position::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl AddAssign for Position {
+    fn add_assign(&mut self, other: Position) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Position {
+    type Output = Position;
+
+    fn sub(self, other: Position) -> Position {
+        Position::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
 impl Position {
     fn new(x: i64, y: i64, z: i64) -> Position {
         Position { x, y, z }
     }
-    fn add(&self, other: &Position) -> Position {
-        Position::new(self.x + other.x, self.y + other.y, self.z + other.z)
-    }
 }
 
 struct Moon {
@@ -56,6 +77,40 @@ impl Moon {
     }
 }
 
+/// Parses a list of moons, one per line, with each line containing the moon's starting position in
+/// the form `<x=-1, y=7, z=3>`.
+fn parse_moons(input: &str) -> Vec<Moon> {
+    input
+        .trim()
+        .lines()
+        .map(|line| {
+            let coords: Vec<i64> = line
+                .trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .split(',')
+                .map(|part| {
+                    let value = part.split('=').nth(1);
+
+                    value
+                        .and_then(|value| value.trim().parse().ok())
+                        .unwrap_or_else(|| panic!("Invalid moon position: {}", line))
+                })
+                .collect();
+
+            match coords.as_slice() {
+                [x, y, z] => Moon::new(*x, *y, *z),
+                _ => panic!("Invalid moon position: {}", line),
+            }
+        })
+        .collect()
+}
+
+/// Reads the moons from the file at the given `path`.
+fn load_moons(path: &str) -> Result<Vec<Moon>, io::Error> {
+    Ok(parse_moons(&fs::read_to_string(path)?))
+}
+
 fn apply_gravity(moons: &mut Vec<Moon>) {
     for i in 0..moons.len() {
         for j in i + 1..moons.len() {
@@ -89,10 +144,23 @@ fn apply_gravity(moons: &mut Vec<Moon>) {
 
 fn apply_velocity(moons: &mut Vec<Moon>) {
     for moon in moons.iter_mut() {
-        moon.position = moon.position.add(&moon.velocity);
+        moon.position += moon.velocity;
+    }
+}
+
+/// Advances the simulation by the given number of steps.
+fn simulate(moons: &mut Vec<Moon>, steps: usize) {
+    for _ in 0..steps {
+        apply_gravity(moons);
+        apply_velocity(moons);
     }
 }
 
+/// Returns the total energy in the system: the sum of the energy of each moon.
+fn total_energy(moons: &[Moon]) -> i64 {
+    moons.iter().map(Moon::energy).sum()
+}
+
 fn gcd(a: i64, b: i64) -> i64 {
     let mut a = a;
     let mut b = b;
@@ -110,109 +178,67 @@ fn lcm(a: i64, b: i64) -> i64 {
     a * b / gcd(a, b)
 }
 
-struct AxisPositionCache {
-    attr_num: usize,
-    seen: HashSet<Vec<(i64, i64)>>,
-    value: Option<usize>,
+/// Takes the position and velocity of each moon along a single axis.
+fn axis(moons: &[Moon], index: usize) -> Vec<(i64, i64)> {
+    moons
+        .iter()
+        .map(|moon| (moon.position[index], moon.velocity[index]))
+        .collect()
 }
 
-impl AxisPositionCache {
-    fn new(attr_num: usize) -> AxisPositionCache {
-        AxisPositionCache {
-            attr_num,
-            seen: HashSet::new(),
-            value: None,
-        }
-    }
+/// Applies gravity and then velocity to the (position, velocity) pairs of an axis. Each axis is
+/// independent of the others, so may be simulated separately.
+fn step_axis(axis: &mut [(i64, i64)]) {
+    for i in 0..axis.len() {
+        for j in i + 1..axis.len() {
+            let pull = (axis[j].0 - axis[i].0).signum();
 
-    fn has_value(&self) -> bool {
-        self.value.is_some()
-    }
-
-    fn key_for(&self, moons: &Vec<Moon>) -> Vec<(i64, i64)> {
-        moons
-            .iter()
-            .map(|moon| (moon.position[self.attr_num], moon.velocity[self.attr_num]))
-            .collect::<Vec<(i64, i64)>>()
+            axis[i].1 += pull;
+            axis[j].1 -= pull;
+        }
     }
 
-    /// Inserts the given key into the cache unless the cache already has a value. If the key has
-    /// been encountered previously, the cache value is set.
-    fn seen_or_insert(&mut self, iteration: usize, key: Vec<(i64, i64)>) {
-        if self.seen.contains(&key) {
-            self.value = Some(iteration);
-        } else {
-            self.seen.insert(key);
-        }
+    for (position, velocity) in axis.iter_mut() {
+        *position += *velocity;
     }
 }
 
-/// Keeps track of previously seen positions and velocities for each axis and once repeats have been
-/// found for all three, calculates the lowest common multiple of all three.
-///
-/// This is a little computationally wasteful since it continues computing axes until a value for
-/// all three has been found. In my input, the first repeat for the Y axis is in step 96236, while
-/// the repeat for X is 231614: this means that 135,378 further Y axis positions and velocities are
-/// calculated by apply_gravity and apply_velocity, even though they aren't needed.
-fn part_two(moons: &mut Vec<Moon>) -> i64 {
+/// Simulates a single axis until it returns to a previously seen combination of positions and
+/// velocities, returning the number of steps taken.
+fn axis_cycle(axis: Vec<(i64, i64)>) -> usize {
+    let mut axis = axis;
+    let mut seen = HashSet::new();
     let mut step = 0;
 
-    let mut x = AxisPositionCache::new(0);
-    let mut y = AxisPositionCache::new(1);
-    let mut z = AxisPositionCache::new(2);
-
-    loop {
-        if !x.has_value() {
-            x.seen_or_insert(step, x.key_for(moons));
-        }
-
-        if !y.has_value() {
-            y.seen_or_insert(step, y.key_for(moons));
-        }
-
-        if !z.has_value() {
-            z.seen_or_insert(step, z.key_for(moons));
-        }
-
-        if x.has_value() && y.has_value() && z.has_value() {
-            return lcm(
-                x.value.unwrap() as i64,
-                lcm(y.value.unwrap() as i64, z.value.unwrap() as i64),
-            );
-        }
-
-        apply_gravity(moons);
-        apply_velocity(moons);
-
+    while seen.insert(axis.clone()) {
+        step_axis(&mut axis);
         step += 1;
     }
+
+    step
 }
 
-fn main() {
-    let mut moons = vec![
-        Moon::new(-1, 7, 3),
-        Moon::new(12, 2, -13),
-        Moon::new(14, 18, -8),
-        Moon::new(17, 4, -4),
-    ];
+/// Finds how many steps it takes for the moons to return to a previous state. Each axis is
+/// simulated independently until it repeats, and the lowest common multiple of the three cycle
+/// lengths is when all three repeat at once.
+fn part_two(moons: &[Moon]) -> i64 {
+    (0..3)
+        .map(|index| axis_cycle(axis(moons, index)) as i64)
+        .fold(1, lcm)
+}
 
-    for _ in 0..1000 {
-        apply_gravity(&mut moons);
-        apply_velocity(&mut moons);
-    }
+fn main() -> Result<(), io::Error> {
+    let mut moons = load_moons("data/moons.txt")?;
 
-    let energy = moons.iter().fold(0, |memo, moon| memo + moon.energy());
+    simulate(&mut moons, 1000);
 
-    println!("Part one: {}", energy);
+    println!("Part one: {}", total_energy(&moons));
 
-    let mut moons = vec![
-        Moon::new(-1, 7, 3),
-        Moon::new(12, 2, -13),
-        Moon::new(14, 18, -8),
-        Moon::new(17, 4, -4),
-    ];
+    let moons = load_moons("data/moons.txt")?;
 
-    println!("Part two: {}", part_two(&mut moons));
+    println!("Part two: {}", part_two(&moons));
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -229,13 +255,52 @@ mod tests {
             z: -7,
         };
 
-        let moved = one.add(&two);
+        let moved = one + two;
 
         assert_eq!(moved.x, 1);
         assert_eq!(moved.y, -10);
         assert_eq!(moved.z, -5);
     }
 
+    #[test]
+    fn test_position_arithmetic() {
+        assert_eq!(
+            Position::new(1, 2, 3) + Position::new(-1, 0, 4),
+            Position::new(0, 2, 7)
+        );
+
+        assert_eq!(
+            Position::new(1, 2, 3) - Position::new(-1, 0, 4),
+            Position::new(2, 2, -1)
+        );
+
+        let mut position = Position::new(1, 2, 3);
+        position += Position::new(1, 1, 1);
+
+        assert_eq!(position, Position::new(2, 3, 4));
+    }
+
+    #[test]
+    fn test_parse_moons() {
+        let moons = parse_moons(
+            "<x=-1, y=0, z=2>
+             <x=2, y=-10, z=-7>
+             <x=4, y=-8, z=8>
+             <x=3, y=5, z=-1>",
+        );
+
+        assert_eq!(moons.len(), 4);
+
+        assert_eq!(moons[0].position, Position::new(-1, 0, 2));
+        assert_eq!(moons[1].position, Position::new(2, -10, -7));
+        assert_eq!(moons[2].position, Position::new(4, -8, 8));
+        assert_eq!(moons[3].position, Position::new(3, 5, -1));
+
+        assert!(moons
+            .iter()
+            .all(|moon| moon.velocity == Position::new(0, 0, 0)));
+    }
+
     #[test]
     fn test_part_one_example() {
         let mut moons = vec![
@@ -287,8 +352,40 @@ mod tests {
         assert_eq!(moons[3].position, Position::new(2, 0, 4));
     }
 
+    #[test]
+    fn test_total_energy_example() {
+        let mut moons = vec![
+            Moon::new(-1, 0, 2),
+            Moon::new(2, -10, -7),
+            Moon::new(4, -8, 8),
+            Moon::new(3, 5, -1),
+        ];
+
+        assert_eq!(total_energy(&moons), 0);
+
+        simulate(&mut moons, 10);
+
+        assert_eq!(total_energy(&moons), 179);
+    }
+
     #[test]
     fn test_part_two_example() {
+        let moons = vec![
+            Moon::new(-1, 0, 2),
+            Moon::new(2, -10, -7),
+            Moon::new(4, -8, 8),
+            Moon::new(3, 5, -1),
+        ];
+
+        assert_eq!(axis_cycle(axis(&moons, 0)), 18);
+        assert_eq!(axis_cycle(axis(&moons, 1)), 28);
+        assert_eq!(axis_cycle(axis(&moons, 2)), 44);
+
+        assert_eq!(part_two(&moons), 2772);
+    }
+
+    #[test]
+    fn test_step_axis() {
         let mut moons = vec![
             Moon::new(-1, 0, 2),
             Moon::new(2, -10, -7),
@@ -296,7 +393,13 @@ mod tests {
             Moon::new(3, 5, -1),
         ];
 
-        assert_eq!(part_two(&mut moons), 2772);
+        let mut x = axis(&moons, 0);
+
+        step_axis(&mut x);
+        apply_gravity(&mut moons);
+        apply_velocity(&mut moons);
+
+        assert_eq!(x, axis(&moons, 0));
     }
 
     #[test]
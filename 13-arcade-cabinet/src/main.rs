@@ -1,6 +1,6 @@
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fmt;
+use std::io;
 
 extern crate intcode;
 use intcode::{Program, ProgramState};
@@ -27,18 +27,107 @@ impl From<i64> for TileType {
     }
 }
 
+impl fmt::Display for TileType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let character = match self {
+            TileType::Blank => ' ',
+            TileType::Wall => '#',
+            TileType::Block => 'x',
+            TileType::Paddle => '-',
+            TileType::Ball => 'o',
+        };
+
+        write!(f, "{}", character)
+    }
+}
+
+/// Describes the state of the game each time the ball moves, from which a strategy decides how to
+/// move the joystick.
+struct Frame {
+    /// The current (x, y) position of the ball.
+    ball: (i64, i64),
+    /// The position of the ball in the previous frame, if there was one.
+    previous_ball: Option<(i64, i64)>,
+    /// The x position of the paddle.
+    paddle_x: i64,
+    /// The row in which the paddle moves, once it has been drawn.
+    paddle_row: Option<i64>,
+    /// The width of the board, including the walls on either side.
+    width: i64,
+}
+
+/// Decides how to move the joystick given the current frame. Returns -1 to tilt the joystick left,
+/// 1 to tilt it right, and 0 to leave it in the neutral position.
+type Strategy = fn(frame: &Frame) -> i64;
+
+/// Returns the joystick movement which brings the paddle towards `target_x`.
+fn move_towards(target_x: i64, paddle_x: i64) -> i64 {
+    if target_x < paddle_x {
+        -1
+    } else if target_x > paddle_x {
+        1
+    } else {
+        0
+    }
+}
+
+/// Moves the paddle towards the current position of the ball.
+fn greedy(frame: &Frame) -> i64 {
+    move_towards(frame.ball.0, frame.paddle_x)
+}
+
+/// Uses the velocity of the ball to predict the column in which it will reach the paddle, and moves
+/// the paddle there. Bounces off the side walls are accounted for, but not those off blocks. Falls
+/// back to the greedy strategy while the ball is travelling upwards or its velocity is unknown.
+fn look_ahead(frame: &Frame) -> i64 {
+    let (ball_x, ball_y) = frame.ball;
+
+    let (previous_x, previous_y, paddle_row) = match (frame.previous_ball, frame.paddle_row) {
+        (Some((x, y)), Some(row)) => (x, y, row),
+        _ => return greedy(frame),
+    };
+
+    if ball_y <= previous_y || frame.width < 4 {
+        return greedy(frame);
+    }
+
+    // The ball will meet the paddle when it reaches the row immediately above it.
+    let steps = paddle_row - 1 - ball_y;
+    let unbounded_x = ball_x + (ball_x - previous_x) * steps;
+
+    // Fold the unbounded position back between the walls to account for bounces.
+    let last_column = frame.width - 3;
+    let period = 2 * last_column;
+    let offset = (unbounded_x - 1).rem_euclid(period);
+
+    let target_x = if offset > last_column {
+        period - offset
+    } else {
+        offset
+    } + 1;
+
+    move_towards(target_x, frame.paddle_x)
+}
+
 struct Arcade {
     program: Program,
-    // This could be swapped out for Canvas from day 11 to support rendering to the console. I think
-    // this would need an implementation of Format for TileType, or swapping back to just using
-    // integers in the canvas.
+    strategy: Strategy,
+    // Set when the ball passes below the paddle, ending the game.
+    ball_lost: bool,
     canvas: HashMap<(i64, i64), TileType>,
 }
 
 impl Arcade {
     fn new(program: Program) -> Arcade {
+        Arcade::with_strategy(program, greedy)
+    }
+
+    /// Creates an Arcade whose joystick is controlled by the given `strategy`.
+    fn with_strategy(program: Program, strategy: Strategy) -> Arcade {
         Arcade {
             program,
+            strategy,
+            ball_lost: false,
             canvas: HashMap::new(),
         }
     }
@@ -50,12 +139,14 @@ impl Arcade {
 
         // Paddle only moves left or right.
         let mut paddle_pos = 0;
+        let mut paddle_row = None;
         let mut ball = None;
+        let mut previous_ball = None;
 
         // The program yields three values before an action should be taken: an x position, a y
         // position, and a tile type.
         loop {
-            match self.program.run() {
+            match self.program.run().unwrap() {
                 ProgramState::Output(value) => {
                     match (x_pos, y_pos) {
                         (None, None) => x_pos = Some(value),
@@ -74,24 +165,37 @@ impl Arcade {
 
                             if tile == TileType::Paddle {
                                 paddle_pos = x;
+                                paddle_row = Some(y);
                             } else if tile == TileType::Ball {
-                                ball = Some(x);
+                                ball = Some((x, y));
+
+                                // The ball has gone past the paddle and can no longer be caught.
+                                if matches!(paddle_row, Some(row) if y > row) {
+                                    self.ball_lost = true;
+                                }
+                            }
+
+                            self.canvas.insert((x, y), tile);
+
+                            if self.ball_lost {
+                                break;
                             }
 
                             if let Some(ball_pos) = ball {
-                                // Provide joystick input to move the paddle underneath the ball.
-                                if ball_pos < paddle_pos {
-                                    self.program.push_input(-1);
-                                } else if ball_pos > paddle_pos {
-                                    self.program.push_input(1);
-                                } else {
-                                    self.program.push_input(0);
-                                }
+                                let frame = Frame {
+                                    ball: ball_pos,
+                                    previous_ball,
+                                    paddle_x: paddle_pos,
+                                    paddle_row,
+                                    width: self.width(),
+                                };
 
+                                // Provide joystick input to move the paddle.
+                                self.program.push_input((self.strategy)(&frame));
+
+                                previous_ball = ball;
                                 ball = None;
                             }
-
-                            self.canvas.insert((x, y), tile);
                         }
                         _ => unreachable!(),
                     }
@@ -103,44 +207,65 @@ impl Arcade {
 
         score
     }
+
+    /// Returns how many blocks remain on the board drawn so far.
+    fn block_count(&self) -> usize {
+        self.canvas
+            .values()
+            .filter(|tile_type| **tile_type == TileType::Block)
+            .count()
+    }
+
+    /// Returns the width of the board drawn so far.
+    fn width(&self) -> i64 {
+        self.canvas.keys().map(|(x, _)| x + 1).max().unwrap_or(0)
+    }
 }
 
-/// Provided with a path to a file containing an intcode program, reads the file and returns a
-/// vector of the intcodes.
-fn read_intcodes(path: &str) -> Vec<i64> {
-    let file = File::open(path).unwrap();
-    let mut reader = BufReader::new(file);
+impl fmt::Display for Arcade {
+    /// Draws the board as it currently stands, with one line per row. Tiles which have not yet been
+    /// drawn by the program are left blank.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.canvas.is_empty() {
+            return Ok(());
+        }
 
-    let mut first_line = String::new();
-    reader.read_line(&mut first_line).unwrap();
+        let min_x = self.canvas.keys().map(|(x, _)| *x).min().unwrap();
+        let max_x = self.canvas.keys().map(|(x, _)| *x).max().unwrap();
+        let min_y = self.canvas.keys().map(|(_, y)| *y).min().unwrap();
+        let max_y = self.canvas.keys().map(|(_, y)| *y).max().unwrap();
 
-    first_line
-        .trim()
-        .split(",")
-        .map(|intcode| intcode.parse::<i64>().unwrap())
-        .collect()
+        for y in min_y..(max_y + 1) {
+            for x in min_x..(max_x + 1) {
+                match self.canvas.get(&(x, y)) {
+                    Some(tile) => write!(f, "{}", tile)?,
+                    None => write!(f, " ")?,
+                }
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
 }
 
-fn main() {
-    let mut intcodes = read_intcodes("data/intcodes.txt");
+fn main() -> Result<(), io::Error> {
+    let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
     let mut arcade = Arcade::new(Program::new(intcodes.clone()));
     arcade.run();
 
-    println!(
-        "Part one: {}",
-        arcade
-            .canvas
-            .values()
-            .filter(|tile_type| **tile_type == TileType::Block)
-            .collect::<Vec<&TileType>>()
-            .len()
-    );
+    println!("Part one: {}", arcade.block_count());
+
+    let mut program = Program::new(intcodes);
 
     // Set first memory address to 2 to play for free.
-    intcodes[0] = 2;
+    program.poke(0, 2);
 
-    let mut arcade = Arcade::new(Program::new(intcodes));
+    let mut arcade = Arcade::with_strategy(program, look_ahead);
     println!("Part two: {}", arcade.run());
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -148,31 +273,109 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_part_one() {
-        let intcodes = read_intcodes("data/intcodes.txt");
+    fn test_part_one() -> Result<(), io::Error> {
+        let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
         let mut arcade = Arcade::new(Program::new(intcodes));
         arcade.run();
 
+        assert_eq!(arcade.block_count(), 369);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() {
+        let mut arcade = Arcade::new(Program::new(vec![99]));
+
+        for x in 0..5 {
+            arcade.canvas.insert((x, 0), TileType::Wall);
+        }
+
+        for y in 1..4 {
+            arcade.canvas.insert((0, y), TileType::Wall);
+            arcade.canvas.insert((4, y), TileType::Wall);
+        }
+
+        arcade.canvas.insert((1, 1), TileType::Block);
+        arcade.canvas.insert((2, 1), TileType::Block);
+        arcade.canvas.insert((3, 1), TileType::Blank);
+        arcade.canvas.insert((2, 2), TileType::Ball);
+        arcade.canvas.insert((3, 3), TileType::Paddle);
+
         assert_eq!(
-            arcade
-                .canvas
-                .values()
-                .filter(|tile_type| **tile_type == TileType::Block)
-                .collect::<Vec<&TileType>>()
-                .len(),
-            369
+            arcade.to_string(),
+            concat!("#####\n", "#xx #\n", "# o #\n", "#  -#\n")
         );
     }
 
     #[test]
-    fn test_part_two() {
-        let mut intcodes = read_intcodes("data/intcodes.txt");
+    fn test_part_two() -> Result<(), io::Error> {
+        let mut program = Program::from_file("data/intcodes.txt")?;
 
         // Set first memory address to 2 to play for free.
-        intcodes[0] = 2;
+        program.poke(0, 2);
 
-        let mut arcade = Arcade::new(Program::new(intcodes));
+        let mut arcade = Arcade::new(program);
 
         assert_eq!(arcade.run(), 19210);
+        assert_eq!(arcade.block_count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part_two_look_ahead() -> Result<(), io::Error> {
+        let mut program = Program::from_file("data/intcodes.txt")?;
+        program.poke(0, 2);
+
+        let mut arcade = Arcade::with_strategy(program, look_ahead);
+
+        assert_eq!(arcade.run(), 19210);
+        assert!(!arcade.ball_lost);
+        assert_eq!(arcade.block_count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_look_ahead_bounces_off_walls() {
+        // The ball is moving down and right, and will bounce off the right wall at x = 8 before
+        // reaching the row above the paddle at x = 6.
+        let frame = Frame {
+            ball: (7, 10),
+            previous_ball: Some((6, 9)),
+            paddle_x: 6,
+            paddle_row: Some(14),
+            width: 10,
+        };
+
+        assert_eq!(look_ahead(&frame), 0);
+
+        let frame = Frame {
+            paddle_x: 8,
+            ..frame
+        };
+
+        assert_eq!(look_ahead(&frame), -1);
+    }
+
+    #[test]
+    fn test_ball_lost() -> Result<(), io::Error> {
+        let mut program = Program::from_file("data/intcodes.txt")?;
+        program.poke(0, 2);
+
+        // Always moves the paddle away from the ball.
+        fn adversarial(frame: &Frame) -> i64 {
+            -greedy(frame)
+        }
+
+        let mut arcade = Arcade::with_strategy(program, adversarial);
+        let score = arcade.run();
+
+        assert!(arcade.ball_lost);
+        assert!(score < 19210);
+        assert!(arcade.block_count() > 0);
+
+        Ok(())
     }
 }
@@ -1,6 +1,19 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fs;
 use std::io;
+use std::process;
+
+/// Describes why reactions could not be parsed, or why FUEL could not be produced from them.
+#[derive(Debug, PartialEq)]
+enum ReactionError {
+    /// A reactant definition was not a quantity followed by a name.
+    InvalidReactant(String),
+    /// A reaction line did not contain inputs and an output separated by "=>".
+    InvalidReaction(String),
+    /// A reactant is required, but is not ORE and no reaction produces it.
+    UnknownReactant(String),
+}
 
 #[derive(Debug, PartialEq, Eq)]
 struct Reactant {
@@ -8,22 +21,27 @@ struct Reactant {
     quantity: i64,
 }
 
-impl From<String> for Reactant {
-    /// Parses a string into a Reactant. Assumes that the string is a valid reaction definition. A
-    /// definition should be a number and a name for the reactant split by whitespace.
+impl TryFrom<String> for Reactant {
+    type Error = ReactionError;
+
+    /// Parses a string into a Reactant. A definition should be a number and a name for the
+    /// reactant split by whitespace.
     ///
-    /// from will panic if the definition string is not in the expected format.
-    fn from(definition: String) -> Reactant {
+    /// Returns an error if the definition string is not in the expected format.
+    fn try_from(definition: String) -> Result<Reactant, ReactionError> {
         let mut split = definition.split_whitespace();
 
-        Reactant {
-            quantity: split
-                .next()
-                .expect("Expected a reaction quantity")
-                .to_string()
-                .parse::<i64>()
-                .expect("Reactant quantity is not a number"),
-            name: split.next().expect("Expected a reaction name").to_string(),
+        let quantity = split
+            .next()
+            .and_then(|quantity| quantity.parse::<i64>().ok());
+        let name = split.next();
+
+        match (quantity, name, split.next()) {
+            (Some(quantity), Some(name), None) => Ok(Reactant {
+                name: name.to_string(),
+                quantity,
+            }),
+            _ => Err(ReactionError::InvalidReactant(definition)),
         }
     }
 }
@@ -34,73 +52,113 @@ struct Reaction {
     output: Reactant,
 }
 
-impl From<String> for Reaction {
+impl TryFrom<String> for Reaction {
+    type Error = ReactionError;
+
     /// Takes a string representing a complete reaction and returns a Struct representing the inputs
     /// and output.
-    fn from(line: String) -> Reaction {
+    fn try_from(line: String) -> Result<Reaction, ReactionError> {
         let mut parts = line.split(" => ");
-        let input_strs = parts.next().expect("Expected input reactants").split(',');
-
-        let mut inputs = Vec::new();
 
         // left hand side is a string of inputs, right hand side is a string representing a single
         // output
+        let (input_strs, output_str) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(inputs), Some(output), None) => (inputs.split(','), output),
+            _ => return Err(ReactionError::InvalidReaction(line)),
+        };
+
+        let mut inputs = Vec::new();
+
         for input_str in input_strs {
-            inputs.push(Reactant::from(input_str.trim().to_string()))
+            inputs.push(Reactant::try_from(input_str.trim().to_string())?)
         }
 
-        Reaction {
+        Ok(Reaction {
             inputs,
-            output: Reactant::from(
-                parts
-                    .next()
-                    .expect("Expected output reactant")
-                    .trim()
-                    .to_string(),
-            ),
-        }
+            output: Reactant::try_from(output_str.trim().to_string())?,
+        })
     }
 }
 
 /// Reads the input containing a list of all reactions and returns a hashmap of the output names to
 /// their Reaction.
-fn parse_input(input: String) -> HashMap<String, Reaction> {
+fn parse_input(input: String) -> Result<HashMap<String, Reaction>, ReactionError> {
     input
         .lines()
         .map(|line| {
-            let reaction = Reaction::from(line.trim().to_string());
-            (reaction.output.name.clone(), reaction)
+            let reaction = Reaction::try_from(line.trim().to_string())?;
+            Ok((reaction.output.name.clone(), reaction))
         })
-        .collect::<HashMap<String, Reaction>>()
+        .collect()
 }
 
-fn ore_from_fuel(reactions: &HashMap<String, Reaction>, fuel_amount: i64) -> i64 {
+/// Describes the outcome of running the reactions needed to produce some amount of FUEL.
+#[derive(Debug)]
+struct ProductionReport {
+    /// The amount of ORE consumed.
+    ore: i64,
+    /// How much of each reaction output was produced, keyed by the output name.
+    produced: HashMap<String, i64>,
+    /// How much of each reaction output was produced but not consumed, keyed by the output name.
+    leftovers: HashMap<String, i64>,
+}
+
+/// Runs every reaction required to produce `fuel_amount` FUEL, reporting how much ORE was consumed
+/// and how much of each resource was produced along the way.
+///
+/// Returns an error if FUEL, or any resource required to make it other than ORE, has no reaction.
+fn produce(
+    reactions: &HashMap<String, Reaction>,
+    fuel_amount: i64,
+) -> Result<ProductionReport, ReactionError> {
+    produce_with_leftovers(reactions, fuel_amount, HashMap::new())
+}
+
+/// Runs every reaction required to produce `fuel_amount` FUEL, as with `produce`, first using up
+/// the `leftovers` from a previous production run before running any reactions.
+fn produce_with_leftovers(
+    reactions: &HashMap<String, Reaction>,
+    fuel_amount: i64,
+    leftovers: HashMap<String, i64>,
+) -> Result<ProductionReport, ReactionError> {
     // Keep track of the name of the resources we want more of.
     let mut wanted_names = Vec::new();
 
-    // Keep track of how much of each resource we want.
-    let mut wanted = HashMap::new();
+    // Keep track of how much of each resource we want. Leftovers are resources we already have,
+    // so start out as negative amounts.
+    let mut wanted: HashMap<String, i64> = leftovers
+        .into_iter()
+        .map(|(name, amount)| (name, -amount))
+        .collect();
+
+    // Keep track of how much of each resource has been produced.
+    let mut produced = HashMap::new();
 
     wanted_names.push("FUEL".to_string());
-    wanted.insert("FUEL".to_string(), fuel_amount);
+    *wanted.entry("FUEL".to_string()).or_insert(0) += fuel_amount;
 
     while let Some(wanted_name) = wanted_names.pop() {
-        let reaction = reactions
-            .get(&wanted_name)
-            .expect(&format!("Expected reaction {} to exist", wanted_name));
+        let reaction = match reactions.get(&wanted_name) {
+            Some(reaction) => reaction,
+            None => return Err(ReactionError::UnknownReactant(wanted_name)),
+        };
 
         // The amount of a resource we need is the amount determined in previous iterations, divided
-        // by however many is produced by the reaction.
-        let needed =
-            ((*wanted.get(&wanted_name).unwrap() as f64) / reaction.output.quantity as f64).ceil();
+        // by however many is produced by the reaction. Leftovers may cover more than we want, in
+        // which case none is needed; running the reaction in reverse would refund its inputs.
+        let needed = ((*wanted.get(&wanted_name).unwrap() as f64)
+            / reaction.output.quantity as f64)
+            .ceil()
+            .max(0.0);
 
         for input in &reaction.inputs {
             // Queue up production of however much of the input is required.
             let required_amount = wanted.entry(input.name.clone()).or_insert(0);
             *required_amount += (needed * input.quantity as f64) as i64;
 
-            // Queue up the input, as long as the reaction exists. If it doesn't, it will be ORE.
-            if reactions.contains_key(&input.name) {
+            // Queue up the input. ORE is the only resource which has no reaction; it is mined
+            // rather than produced.
+            if input.name != "ORE" {
                 wanted_names.push(input.name.clone())
             }
         }
@@ -109,57 +167,157 @@ fn ore_from_fuel(reactions: &HashMap<String, Reaction>, fuel_amount: i64) -> i64
         // is no longer needed.
         let wanted_output_amount = wanted.entry(reaction.output.name.clone()).or_insert(0);
         *wanted_output_amount -= (needed as i64) * reaction.output.quantity;
+
+        if needed > 0.0 {
+            *produced.entry(reaction.output.name.clone()).or_insert(0) +=
+                (needed as i64) * reaction.output.quantity;
+        }
     }
 
-    *wanted.get(&"ORE".to_string()).expect("Expected ORE amount")
+    // No ORE is needed when the leftovers cover everything.
+    let ore = wanted.remove("ORE").unwrap_or(0);
+
+    let leftovers = wanted
+        .into_iter()
+        .filter(|(_, amount)| *amount < 0)
+        .map(|(name, amount)| (name, -amount))
+        .collect();
+
+    Ok(ProductionReport {
+        ore,
+        produced,
+        leftovers,
+    })
+}
+
+fn ore_from_fuel(
+    reactions: &HashMap<String, Reaction>,
+    fuel_amount: i64,
+) -> Result<i64, ReactionError> {
+    Ok(produce(reactions, fuel_amount)?.ore)
+}
+
+/// Returns how much ORE is needed to produce `fuel` FUEL one at a time, carrying the leftovers from
+/// each unit over to the next. This should always match `ore_from_fuel`, which produces all the
+/// FUEL at once.
+fn ore_for_fuel_batches(
+    reactions: &HashMap<String, Reaction>,
+    fuel: i64,
+) -> Result<i64, ReactionError> {
+    let mut ore = 0;
+    let mut leftovers = HashMap::new();
+
+    for _ in 0..fuel {
+        let report = produce_with_leftovers(reactions, 1, leftovers)?;
+
+        ore += report.ore;
+        leftovers = report.leftovers;
+    }
+
+    Ok(ore)
+}
+
+/// Returns how much of each resource must be produced in order to make `fuel` FUEL, keyed by the
+/// resource name. Amounts are rounded up to whole reactions, so include any leftovers which were
+/// carried over to later reactions. ORE is mined rather than produced, and is not included.
+fn production_plan(
+    reactions: &HashMap<String, Reaction>,
+    fuel: i64,
+) -> Result<HashMap<String, i64>, ReactionError> {
+    Ok(produce(reactions, fuel)?.produced)
+}
+
+/// Returns the names of the reactions whose output is never needed when producing `fuel_amount`
+/// FUEL, sorted alphabetically.
+fn unused_reactions(
+    reactions: &HashMap<String, Reaction>,
+    fuel_amount: i64,
+) -> Result<Vec<String>, ReactionError> {
+    let plan = production_plan(reactions, fuel_amount)?;
+
+    let mut unused: Vec<String> = reactions
+        .keys()
+        .filter(|name| !plan.contains_key(*name))
+        .cloned()
+        .collect();
+
+    unused.sort();
+    Ok(unused)
 }
 
 /// Takes a map of reactions and returns how many ORE are required to produce one FUEL.
-fn part_one(reactions: HashMap<String, Reaction>) -> i64 {
-    ore_from_fuel(&reactions, 1)
+fn part_one(reactions: &HashMap<String, Reaction>) -> Result<i64, ReactionError> {
+    ore_from_fuel(reactions, 1)
 }
 
 /// Do a binary search to see how much FUEL is produced by the target amount of ORE.
-fn part_two(reactions: HashMap<String, Reaction>, target: i64) -> i64 {
-    // Find the minimum amount of ore which would be needed for one unit of fuel.
-    let mut low = target / ore_from_fuel(&reactions, 1);
+fn part_two(reactions: &HashMap<String, Reaction>, target: i64) -> Result<i64, ReactionError> {
+    let ore_per_fuel = ore_from_fuel(reactions, 1)?;
 
-    // Best case scenario one fuel comes from one ore.
-    let mut high = target;
+    if ore_per_fuel > target {
+        return Ok(0);
+    }
 
-    while high > low {
-        let mid = (high + low) / 2;
+    // Leftovers from one unit of fuel can only reduce the ore needed for the next, so this amount
+    // can always be produced.
+    let mut low = target / ore_per_fuel;
 
-        if mid == low {
-            break;
-        }
+    // Find an amount of fuel which can't be produced, doubling until one is found.
+    let mut high = low + 1;
 
-        let ore = ore_from_fuel(&reactions, mid);
+    while ore_from_fuel(reactions, high)? <= target {
+        low = high;
+        high *= 2;
+    }
 
-        if ore > target {
+    // The target amount of ore is always enough for the low amount of fuel, and never enough for
+    // the high amount.
+    while high - low > 1 {
+        let mid = (high + low) / 2;
+
+        if ore_from_fuel(reactions, mid)? > target {
             high = mid;
         } else {
             low = mid;
         }
     }
 
-    low
+    debug_assert!(ore_from_fuel(reactions, low + 1)? > target);
+
+    Ok(low)
 }
 
-fn main() -> Result<(), io::Error> {
-    let data = fs::read_to_string("data/reactions.txt")?;
-    let data = data.trim();
+/// Prints the answers to both parts, along with what is left over from producing one FUEL.
+fn report(reactions: &HashMap<String, Reaction>) -> Result<(), ReactionError> {
+    println!("Part one: {:?}", part_one(reactions)?);
+    println!("Part two: {:?}", part_two(reactions, 1_000_000_000_000)?);
+    println!("Unused reactions: {:?}", unused_reactions(reactions, 1)?);
+
+    let mut leftovers: Vec<_> = produce(reactions, 1)?.leftovers.into_iter().collect();
 
-    println!("Part one: {:?}", part_one(parse_input(data.to_string())));
+    leftovers.sort();
 
     println!(
-        "Part two: {:?}",
-        part_two(parse_input(data.to_string()), 1_000_000_000_000)
+        "Leftovers from one FUEL: {:?} (two FUEL needs {} ORE)",
+        leftovers,
+        ore_for_fuel_batches(reactions, 2)?
     );
 
     Ok(())
 }
 
+fn main() -> Result<(), io::Error> {
+    let data = fs::read_to_string("data/reactions.txt")?;
+
+    if let Err(err) = parse_input(data.trim().to_string()).and_then(|reactions| report(&reactions))
+    {
+        eprintln!("Error: {:?}", err);
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,7 +330,7 @@ mod tests {
     #[test]
     fn test_reactant_from_string() {
         assert_eq!(
-            Reactant::from("2 A".to_string()),
+            Reactant::try_from("2 A".to_string()).unwrap(),
             Reactant {
                 name: "A".to_string(),
                 quantity: 2
@@ -180,7 +338,7 @@ mod tests {
         );
 
         assert_eq!(
-            Reactant::from("6 AX".to_string()),
+            Reactant::try_from("6 AX".to_string()).unwrap(),
             Reactant {
                 name: "AX".to_string(),
                 quantity: 6
@@ -191,7 +349,7 @@ mod tests {
     #[test]
     fn test_reaction_from_string() {
         assert_eq!(
-            Reaction::from("10 ORE => 2 A".to_string()),
+            Reaction::try_from("10 ORE => 2 A".to_string()).unwrap(),
             Reaction {
                 inputs: vec![Reactant {
                     name: "ORE".to_string(),
@@ -205,7 +363,7 @@ mod tests {
         );
 
         assert_eq!(
-            Reaction::from("10 ORE, 2 A => 1 B".to_string()),
+            Reaction::try_from("10 ORE, 2 A => 1 B".to_string()).unwrap(),
             Reaction {
                 inputs: vec![
                     Reactant {
@@ -236,7 +394,7 @@ mod tests {
              7 A, 1 E => 1 FUEL",
         );
 
-        let parsed = parse_input(input);
+        let parsed = parse_input(input).unwrap();
 
         assert_eq!(
             parsed.get("FUEL"),
@@ -282,9 +440,10 @@ mod tests {
              7 A, 1 C => 1 D
              7 A, 1 D => 1 E
              7 A, 1 E => 1 FUEL",
-        ));
+        ))
+        .unwrap();
 
-        assert_eq!(part_one(reactions), 31);
+        assert_eq!(part_one(&reactions).unwrap(), 31);
 
         let reactions = parse_input(trim_leading_whitespace(
             "9 ORE => 2 A
@@ -294,9 +453,10 @@ mod tests {
              5 B, 7 C => 1 BC
              4 C, 1 A => 1 CA
              2 AB, 3 BC, 4 CA => 1 FUEL",
-        ));
+        ))
+        .unwrap();
 
-        assert_eq!(part_one(reactions), 165);
+        assert_eq!(part_one(&reactions).unwrap(), 165);
 
         let reactions = parse_input(trim_leading_whitespace(
             "157 ORE => 5 NZVS
@@ -308,9 +468,10 @@ mod tests {
              7 DCFZ, 7 PSHF => 2 XJWVT
              165 ORE => 2 GPVTF
              3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT",
-        ));
+        ))
+        .unwrap();
 
-        assert_eq!(part_one(reactions), 13312);
+        assert_eq!(part_one(&reactions).unwrap(), 13312);
 
         let reactions = parse_input(trim_leading_whitespace(
             "2 VPVL, 7 FWMGM, 2 CXFTF, 11 MNCFX => 1 STKFG
@@ -325,9 +486,10 @@ mod tests {
              1 NVRVD => 8 CXFTF
              1 VJHF, 6 MNCFX => 4 RFSQX
              176 ORE => 6 VJHF",
-        ));
+        ))
+        .unwrap();
 
-        assert_eq!(part_one(reactions), 180697);
+        assert_eq!(part_one(&reactions).unwrap(), 180697);
 
         let reactions = parse_input(trim_leading_whitespace(
             "171 ORE => 8 CNZTR
@@ -347,9 +509,10 @@ mod tests {
              121 ORE => 7 VRPVC
              7 XCVML => 6 RJRHP
              5 BHXH, 4 VRPVC => 5 LTCX",
-        ));
+        ))
+        .unwrap();
 
-        assert_eq!(part_one(reactions), 2210736);
+        assert_eq!(part_one(&reactions).unwrap(), 2210736);
     }
 
     #[test]
@@ -364,9 +527,10 @@ mod tests {
              7 DCFZ, 7 PSHF => 2 XJWVT
              165 ORE => 2 GPVTF
              3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT",
-        ));
+        ))
+        .unwrap();
 
-        assert_eq!(part_two(reactions, 1_000_000_000_000), 82892753);
+        assert_eq!(part_two(&reactions, 1_000_000_000_000).unwrap(), 82892753);
 
         let reactions = parse_input(trim_leading_whitespace(
             "2 VPVL, 7 FWMGM, 2 CXFTF, 11 MNCFX => 1 STKFG
@@ -381,9 +545,10 @@ mod tests {
              1 NVRVD => 8 CXFTF
              1 VJHF, 6 MNCFX => 4 RFSQX
              176 ORE => 6 VJHF",
-        ));
+        ))
+        .unwrap();
 
-        assert_eq!(part_two(reactions, 1_000_000_000_000), 5586022);
+        assert_eq!(part_two(&reactions, 1_000_000_000_000).unwrap(), 5586022);
 
         let reactions = parse_input(trim_leading_whitespace(
             "171 ORE => 8 CNZTR
@@ -403,8 +568,197 @@ mod tests {
              121 ORE => 7 VRPVC
              7 XCVML => 6 RJRHP
              5 BHXH, 4 VRPVC => 5 LTCX",
-        ));
+        ))
+        .unwrap();
+
+        assert_eq!(part_two(&reactions, 1_000_000_000_000).unwrap(), 460664);
+    }
+
+    #[test]
+    fn test_part_two_edges() {
+        let reactions = parse_input("1 ORE => 1 FUEL".to_string()).unwrap();
+        assert_eq!(part_two(&reactions, 1_000_000).unwrap(), 1_000_000);
+
+        let reactions = parse_input("10 ORE => 1 FUEL".to_string()).unwrap();
+        assert_eq!(part_two(&reactions, 5).unwrap(), 0);
+
+        let reactions = parse_input("10 ORE => 1 FUEL".to_string()).unwrap();
+        assert_eq!(part_two(&reactions, 29).unwrap(), 2);
+
+        // More fuel than ore is produced, as each ORE gives 10 A.
+        let reactions = parse_input(trim_leading_whitespace(
+            "1 ORE => 10 A
+             1 A => 1 FUEL",
+        ))
+        .unwrap();
+
+        assert_eq!(part_two(&reactions, 5).unwrap(), 50);
+    }
+
+    #[test]
+    fn test_invalid_reactions() {
+        assert_eq!(
+            Reactant::try_from("A 2".to_string()),
+            Err(ReactionError::InvalidReactant("A 2".to_string()))
+        );
+
+        assert_eq!(
+            Reaction::try_from("10 ORE 2 A".to_string()),
+            Err(ReactionError::InvalidReaction("10 ORE 2 A".to_string()))
+        );
+
+        assert_eq!(
+            parse_input("10 ORE => 2".to_string()).err(),
+            Some(ReactionError::InvalidReactant("2".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_unknown_reactant() {
+        // Nothing produces B.
+        let reactions = parse_input(trim_leading_whitespace(
+            "10 ORE => 2 A
+             1 A, 2 B => 1 FUEL",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            ore_from_fuel(&reactions, 1),
+            Err(ReactionError::UnknownReactant("B".to_string()))
+        );
+
+        let reactions = parse_input("10 ORE => 2 A".to_string()).unwrap();
+
+        assert_eq!(
+            part_one(&reactions),
+            Err(ReactionError::UnknownReactant("FUEL".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_leftovers() {
+        let reactions = parse_input(trim_leading_whitespace(
+            "10 ORE => 10 A
+             1 ORE => 1 B
+             7 A, 1 B => 1 C
+             7 A, 1 C => 1 D
+             7 A, 1 D => 1 E
+             7 A, 1 E => 1 FUEL",
+        ))
+        .unwrap();
+
+        let report = produce(&reactions, 1).unwrap();
+
+        assert_eq!(report.leftovers.len(), 1);
+        assert_eq!(report.leftovers.get("A"), Some(&2));
+
+        // Two A are left over from the first run, so another 10 ORE are needed for the remaining
+        // 26, leaving 4 A.
+        let report = produce_with_leftovers(&reactions, 1, report.leftovers).unwrap();
+
+        assert_eq!(report.ore, 31);
+        assert_eq!(report.leftovers.get("A"), Some(&4));
+
+        // Leftovers larger than a whole batch don't refund any ORE.
+        let mut leftovers = HashMap::new();
+        leftovers.insert("A".to_string(), 40);
+
+        let report = produce_with_leftovers(&reactions, 1, leftovers).unwrap();
+
+        assert_eq!(report.ore, 1);
+        assert_eq!(report.leftovers.get("A"), Some(&12));
+
+        let mut leftovers = HashMap::new();
+        leftovers.insert("FUEL".to_string(), 2);
+
+        let report = produce_with_leftovers(&reactions, 1, leftovers).unwrap();
+
+        assert_eq!(report.ore, 0);
+        assert_eq!(report.leftovers.get("FUEL"), Some(&1));
+    }
+
+    #[test]
+    fn test_ore_for_fuel_batches() {
+        let reactions = parse_input(trim_leading_whitespace(
+            "9 ORE => 2 A
+             8 ORE => 3 B
+             7 ORE => 5 C
+             3 A, 4 B => 1 AB
+             5 B, 7 C => 1 BC
+             4 C, 1 A => 1 CA
+             2 AB, 3 BC, 4 CA => 1 FUEL",
+        ))
+        .unwrap();
+
+        let one = ore_for_fuel_batches(&reactions, 1).unwrap();
+        let two = ore_for_fuel_batches(&reactions, 2).unwrap();
+
+        assert_eq!(one, 165);
+        assert!(two < 2 * one);
+
+        for fuel in 1..10 {
+            assert_eq!(
+                ore_for_fuel_batches(&reactions, fuel),
+                ore_from_fuel(&reactions, fuel)
+            );
+        }
+    }
+
+    #[test]
+    fn test_production_plan() {
+        let reactions = parse_input(trim_leading_whitespace(
+            "10 ORE => 10 A
+             1 ORE => 1 B
+             7 A, 1 B => 1 C
+             7 A, 1 C => 1 D
+             7 A, 1 D => 1 E
+             7 A, 1 E => 1 FUEL",
+        ))
+        .unwrap();
+
+        let plan = production_plan(&reactions, 1).unwrap();
+
+        // 28 A are consumed, but the reaction produces them in batches of 10.
+        assert_eq!(plan.get("A"), Some(&30));
+        assert_eq!(plan.get("B"), Some(&1));
+        assert_eq!(plan.get("C"), Some(&1));
+        assert_eq!(plan.get("D"), Some(&1));
+        assert_eq!(plan.get("E"), Some(&1));
+        assert_eq!(plan.get("FUEL"), Some(&1));
+        assert_eq!(plan.get("ORE"), None);
+
+        assert_eq!(production_plan(&reactions, 2).unwrap().get("A"), Some(&60));
+    }
+
+    #[test]
+    fn test_unused_reactions() {
+        let reactions = parse_input(trim_leading_whitespace(
+            "10 ORE => 10 A
+             1 ORE => 1 B
+             7 A, 1 B => 1 C
+             7 A, 1 C => 1 D
+             3 ORE => 1 X
+             2 X, 1 B => 1 Y
+             7 A, 1 D => 1 E
+             7 A, 1 E => 1 FUEL",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            unused_reactions(&reactions, 1).unwrap(),
+            vec!["X".to_string(), "Y".to_string()]
+        );
+
+        let reactions = parse_input(trim_leading_whitespace(
+            "10 ORE => 10 A
+             1 ORE => 1 B
+             7 A, 1 B => 1 C
+             7 A, 1 C => 1 D
+             7 A, 1 D => 1 E
+             7 A, 1 E => 1 FUEL",
+        ))
+        .unwrap();
 
-        assert_eq!(part_two(reactions, 1_000_000_000_000), 460664);
+        assert!(unused_reactions(&reactions, 1).unwrap().is_empty());
     }
 }
@@ -1,8 +1,6 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
 
-extern crate rand;
-
 extern crate intcode;
 use intcode::{Program, ProgramState};
 
@@ -53,14 +51,13 @@ enum Direction {
 }
 
 impl Direction {
-    /// Returns a direction randomly.
-    fn rand() -> Direction {
-        match rand::random::<usize>() % 4 {
-            0 => Direction::Up,
-            1 => Direction::Down,
-            2 => Direction::Left,
-            3 => Direction::Right,
-            _ => unreachable!(),
+    /// Returns the direction which undoes a movement in this direction.
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
         }
     }
 
@@ -95,9 +92,6 @@ impl Canvas {
     }
 
     /// Returns if the given position may be visited by the robot.
-    ///
-    /// TODO: If the desired position is surrounded by None or Wall, the cell is not visitable.
-    ///       Could this be used to exit the map building phase earlier?
     fn visitable(&self, position: &Pos) -> bool {
         if let Some(Cell::Wall) = self.0.get(position) {
             return false;
@@ -106,6 +100,29 @@ impl Canvas {
         true
     }
 
+    /// Returns if every cell which can be reached from `position` is known, and all of their
+    /// neighbors are also known. Once true, exploring further will reveal nothing new.
+    fn is_fully_explored(&self, position: Pos) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![position];
+
+        while let Some(pos) = stack.pop() {
+            if !visited.insert(pos) {
+                continue;
+            }
+
+            for neighbor in pos.visitable_neighbors(self) {
+                if !self.0.contains_key(&neighbor) {
+                    return false;
+                }
+
+                stack.push(neighbor);
+            }
+        }
+
+        true
+    }
+
     /// Calculates the shorted path from the start position to the target position. Returns None if
     /// no path could be found.
     fn shortest_path(&self, start: Pos, target: Pos) -> Option<usize> {
@@ -161,56 +178,74 @@ impl Canvas {
     }
 }
 
-/// Takes the intcode program and moves the robot randomly a million times to create the map.
-/// Returns the completed map and the position of the oxygen system.
+/// Instructs the robot to move one step in the given direction, returning the status code it
+/// reports: 0 if it hit a wall, 1 if it moved, or 2 if it moved and found the oxygen system.
+fn move_robot(program: &mut Program, direction: &Direction) -> i64 {
+    program.push_input(direction.as_input());
+
+    match program.run().unwrap() {
+        ProgramState::Output(value) => value,
+        state => panic!("Expected the robot to report its status, got {:?}", state),
+    }
+}
+
+/// Takes the intcode program and explores with the robot depth-first -- backtracking whenever it
+/// reaches a position whose neighbors are all known -- until the whole map is explored. Returns
+/// the completed map and the position of the oxygen system.
 fn build_map(program: Program) -> (Canvas, Pos) {
     let mut program = program;
     let mut map = Canvas::new();
 
     let mut position = Pos(0, 0);
-    let mut direction = Direction::rand();
-    let mut next_position = position.travel(&direction);
-
     let mut oxy_pos = None;
 
-    program.push_input(direction.as_input());
+    // The directions taken from the start to reach the current position; reversing them takes the
+    // robot back along the way it came.
+    let mut path = Vec::new();
+
+    map.0.insert(position, Cell::Empty);
 
-    // Stole this trick from someone else. My map input produces cell types for 1657 map positions,
-    // so we can stop the loop as soon as all positions are known. This value may differ for other
-    // inputs.
-    while map.0.len() < 1657 {
-        match program.run() {
-            ProgramState::Output(value) => match value {
+    loop {
+        let unexplored = (1..5)
+            .map(Direction::from)
+            .find(|direction| !map.0.contains_key(&position.travel(direction)));
+
+        if let Some(direction) = unexplored {
+            let next_position = position.travel(&direction);
+
+            match move_robot(&mut program, &direction) {
                 0 => {
                     map.0.insert(next_position, Cell::Wall);
                 }
                 1 => {
                     map.0.insert(next_position, Cell::Empty);
                     position = next_position;
+                    path.push(direction);
                 }
                 2 => {
                     map.0.insert(next_position, Cell::OxygenSystem);
                     position = next_position;
                     oxy_pos = Some(position);
+                    path.push(direction);
                 }
-                _ => unreachable!(),
-            },
-            ProgramState::Wait => panic!("No input available"),
-            ProgramState::Halt => break,
-        }
-
-        // Move the robot in a random direction to uncover more of the map.
-        direction = Direction::rand();
-        next_position = position.travel(&direction);
-
-        while !map.visitable(&next_position) {
-            direction = Direction::rand();
-            next_position = position.travel(&direction);
+                status => panic!("Unknown robot status: {}", status),
+            }
+        } else if let Some(direction) = path.pop() {
+            let back = direction.opposite();
+
+            move_robot(&mut program, &back);
+            position = position.travel(&back);
+        } else {
+            // Back at the start with nothing left to explore.
+            break;
         }
-
-        program.push_input(direction.as_input());
     }
 
+    assert!(
+        map.is_fully_explored(Pos(0, 0)),
+        "Expected the robot to have explored the whole map"
+    );
+
     (map, oxy_pos.expect("Expected to find oxygen system!"))
 }
 
@@ -243,6 +278,46 @@ fn main() -> Result<(), io::Error> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_fully_explored() {
+        // #####
+        // #..O#
+        // ##?##
+        let mut map = Canvas::new();
+
+        for x in 0..5 {
+            map.0.insert(Pos(x, 0), Cell::Wall);
+        }
+
+        map.0.insert(Pos(0, 1), Cell::Wall);
+        map.0.insert(Pos(1, 1), Cell::Empty);
+        map.0.insert(Pos(2, 1), Cell::Empty);
+        map.0.insert(Pos(3, 1), Cell::OxygenSystem);
+        map.0.insert(Pos(4, 1), Cell::Wall);
+
+        map.0.insert(Pos(0, 2), Cell::Wall);
+        map.0.insert(Pos(1, 2), Cell::Wall);
+        map.0.insert(Pos(3, 2), Cell::Wall);
+        map.0.insert(Pos(4, 2), Cell::Wall);
+
+        assert!(!map.is_fully_explored(Pos(1, 1)));
+
+        map.0.insert(Pos(2, 2), Cell::Wall);
+
+        assert!(map.is_fully_explored(Pos(1, 1)));
+    }
+
+    #[test]
+    fn test_build_map_is_deterministic() -> Result<(), io::Error> {
+        let (first_map, first_oxy_pos) = build_map(Program::from_file("data/intcodes.txt")?);
+        let (second_map, second_oxy_pos) = build_map(Program::from_file("data/intcodes.txt")?);
+
+        assert_eq!(first_map.0.len(), second_map.0.len());
+        assert_eq!(first_oxy_pos, second_oxy_pos);
+
+        Ok(())
+    }
+
     #[test]
     fn test_part_one() -> Result<(), io::Error> {
         assert_eq!(
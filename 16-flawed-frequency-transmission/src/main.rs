@@ -1,8 +1,9 @@
-#![feature(repeat_generic_slice)]
-
 use std::fs;
 use std::io;
 
+extern crate rayon;
+use rayon::prelude::*;
+
 /// Takes a vector and cycles through each element in turn. Once it reaches the end of the vector it
 /// yields the first value again, and so on. A `repeat` may be provided; each element in the vector
 /// will be repeated this many times before proceeding to the next.
@@ -24,6 +25,17 @@ impl RepeatingCycleIterator {
             values,
         }
     }
+
+    /// Returns the number of values yielded before the sequence starts over.
+    fn period(&self) -> usize {
+        self.values.len() * self.repeat
+    }
+
+    /// Returns the iterator to the start of the sequence, so that it may be used again.
+    fn reset(&mut self) {
+        self.current_repeat = 1;
+        self.iterations = 0;
+    }
 }
 
 impl Iterator for RepeatingCycleIterator {
@@ -46,6 +58,10 @@ impl Iterator for RepeatingCycleIterator {
     }
 }
 
+/// The straightforward implementation of FFT, which builds a new pattern iterator for every digit in
+/// every phase. Kept as a reference against which [`FftPlanner`] and the parallel implementation
+/// are tested.
+#[cfg(test)]
 fn flawed_frequency_transmission(transmission: Vec<i32>, iterations: usize) -> Vec<i32> {
     let mut transmission = transmission;
     let base_pattern = vec![0, 1, 0, -1];
@@ -76,21 +92,117 @@ fn flawed_frequency_transmission(transmission: Vec<i32>, iterations: usize) -> V
     transmission
 }
 
+/// Calculates each digit of every phase of FFT in parallel. Each digit in a phase depends only on the
+/// digits of the previous phase, so they can be calculated independently of one another.
+fn flawed_frequency_transmission_parallel(transmission: Vec<i32>, iterations: usize) -> Vec<i32> {
+    let mut transmission = transmission;
+
+    // Build the pattern for each digit once, resetting them in each phase.
+    let mut patterns = (0..transmission.len())
+        .map(|i| RepeatingCycleIterator::new(vec![0, 1, 0, -1], i + 1))
+        .collect::<Vec<_>>();
+
+    for _ in 0..iterations {
+        transmission = patterns
+            .par_iter_mut()
+            .map(|pattern| {
+                pattern.reset();
+
+                // Discard the first pattern value.
+                pattern.next();
+
+                let sum: i32 = transmission
+                    .iter()
+                    .zip(pattern)
+                    .map(|(value, pattern)| value * pattern)
+                    .sum();
+
+                sum.abs() % 10
+            })
+            .collect();
+    }
+
+    transmission
+}
+
+/// Precomputes the pattern used to calculate each digit of a signal with a known length. The same
+/// planner may then be applied to any number of signals of that length, for any number of phases,
+/// without rebuilding the patterns each time.
+struct FftPlanner {
+    rows: Vec<Vec<i32>>,
+}
+
+impl FftPlanner {
+    fn new(len: usize) -> FftPlanner {
+        let rows = (0..len)
+            .map(|i| {
+                let pattern = RepeatingCycleIterator::new(vec![0, 1, 0, -1], i + 1);
+                let period = pattern.period();
+
+                // Build one period of the pattern and cycle it to fill the row, discarding the
+                // first pattern value.
+                let cycle = pattern.take(period).collect::<Vec<i32>>();
+
+                cycle.iter().cycle().skip(1).take(len).copied().collect()
+            })
+            .collect();
+
+        FftPlanner { rows }
+    }
+
+    /// Runs the given number of FFT phases on the `transmission`, whose length must match that
+    /// given when creating the planner.
+    fn apply(&self, transmission: Vec<i32>, iterations: usize) -> Vec<i32> {
+        assert_eq!(transmission.len(), self.rows.len());
+
+        let mut transmission = transmission;
+
+        for _ in 0..iterations {
+            transmission = self
+                .rows
+                .iter()
+                .map(|row| {
+                    let sum: i32 = row
+                        .iter()
+                        .zip(&transmission)
+                        .map(|(pattern, value)| pattern * value)
+                        .sum();
+
+                    sum.abs() % 10
+                })
+                .collect();
+        }
+
+        transmission
+    }
+}
+
+/// Returns the eight digits starting at `offset` after running the given number of FFT phases.
+///
 /// Cheats by assuming that the repeating pattern is always 1 for the digits in the transmission
-/// which we need to sum.
+/// which we need to sum. This only holds when the offset is in the second half of the
+/// transmission; for smaller offsets the full, much slower, calculation is used instead.
+///
+/// Panics if there are fewer than eight digits from the offset to the end of the transmission.
 fn flawed_frequency_transmission_with_offset(
     transmission: Vec<i32>,
     iterations: usize,
     offset: usize,
 ) -> Vec<i32> {
-    let len = transmission.len() - offset - 1;
+    assert!(
+        offset + 8 <= transmission.len(),
+        "Offset {} leaves fewer than 8 digits in a transmission of length {}",
+        offset,
+        transmission.len()
+    );
+
+    if offset < transmission.len() / 2 {
+        let transmission = flawed_frequency_transmission_parallel(transmission, iterations);
+        return transmission[offset..offset + 8].to_vec();
+    }
 
-    let mut transmission = transmission
-        .into_iter()
-        .cycle()
-        .skip(offset)
-        .take(len)
-        .collect::<Vec<i32>>();
+    let mut transmission = transmission[offset..].to_vec();
+    let len = transmission.len();
 
     for _ in 0..iterations {
         for i in (0..len - 1).rev() {
@@ -126,7 +238,8 @@ fn vec_to_number(numbers: Vec<i32>) -> i32 {
 }
 
 fn part_one(transmission: Vec<i32>) -> String {
-    flawed_frequency_transmission(transmission, 100)
+    FftPlanner::new(transmission.len())
+        .apply(transmission, 100)
         .into_iter()
         .take(8)
         .map(|num| format!("{}", num))
@@ -196,6 +309,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cycle_iterator_period() {
+        let iter = RepeatingCycleIterator::new(vec![0, 1, 0, -1], 3);
+        assert_eq!(iter.period(), 12);
+
+        let iter = RepeatingCycleIterator::new(vec![1, 2, 3], 1);
+        assert_eq!(iter.period(), 3);
+    }
+
+    #[test]
+    fn test_cycle_iterator_reset() {
+        let mut iter = RepeatingCycleIterator::new(vec![0, 1, 0, -1], 3);
+        let expected = vec![0, 0, 0, 1, 1, 1, 0, 0, 0, -1, -1, -1, 0, 0];
+
+        assert_eq!(iter.by_ref().take(5).collect::<Vec<i32>>(), expected[..5]);
+
+        iter.reset();
+
+        assert_eq!(iter.take(14).collect::<Vec<i32>>(), expected);
+    }
+
     #[test]
     fn test_vec_to_number() {
         assert_eq!(vec_to_number(vec![1, 2, 3, 4,]), 1234);
@@ -257,6 +391,91 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_planner() {
+        let signals = vec![
+            vec![1, 2, 3, 4, 5, 6, 7, 8],
+            vec![
+                8, 0, 8, 7, 1, 2, 2, 4, 5, 8, 5, 9, 1, 4, 5, 4, 6, 6, 1, 9, 0, 8, 3, 2, 1, 8, 6, 4,
+                5, 5, 9, 5,
+            ],
+            vec![
+                1, 9, 6, 1, 7, 8, 0, 4, 2, 0, 7, 2, 0, 2, 2, 0, 9, 1, 4, 4, 9, 1, 6, 0, 4, 4, 1, 8,
+                9, 9, 1, 7,
+            ],
+            vec![
+                6, 9, 3, 1, 7, 1, 6, 3, 4, 9, 2, 9, 4, 8, 6, 0, 6, 3, 3, 5, 9, 9, 5, 9, 2, 4, 3, 1,
+                9, 8, 7, 3,
+            ],
+        ];
+
+        for signal in signals {
+            let planner = FftPlanner::new(signal.len());
+
+            for iterations in &[1, 4, 100] {
+                assert_eq!(
+                    planner.apply(signal.clone(), *iterations),
+                    flawed_frequency_transmission(signal.clone(), *iterations)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel() {
+        let signals = vec![
+            vec![
+                8, 0, 8, 7, 1, 2, 2, 4, 5, 8, 5, 9, 1, 4, 5, 4, 6, 6, 1, 9, 0, 8, 3, 2, 1, 8, 6, 4,
+                5, 5, 9, 5,
+            ],
+            vec![
+                1, 9, 6, 1, 7, 8, 0, 4, 2, 0, 7, 2, 0, 2, 2, 0, 9, 1, 4, 4, 9, 1, 6, 0, 4, 4, 1, 8,
+                9, 9, 1, 7,
+            ],
+            vec![
+                6, 9, 3, 1, 7, 1, 6, 3, 4, 9, 2, 9, 4, 8, 6, 0, 6, 3, 3, 5, 9, 9, 5, 9, 2, 4, 3, 1,
+                9, 8, 7, 3,
+            ],
+        ];
+
+        for signal in signals {
+            assert_eq!(
+                flawed_frequency_transmission_parallel(signal.clone(), 100),
+                flawed_frequency_transmission(signal, 100)
+            );
+        }
+    }
+
+    #[test]
+    fn test_planner_real_transmission() -> Result<(), io::Error> {
+        let transmission = read_transmission("data/transmission.txt")?;
+        let planner = FftPlanner::new(transmission.len());
+
+        assert_eq!(
+            planner.apply(transmission.clone(), 100),
+            flawed_frequency_transmission(transmission, 100)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signal_with_offset() {
+        let signal = vec![
+            8, 0, 8, 7, 1, 2, 2, 4, 5, 8, 5, 9, 1, 4, 5, 4, 6, 6, 1, 9, 0, 8, 3, 2, 1, 8, 6, 4, 5,
+            5, 9, 5,
+        ];
+
+        // Offsets in the first half of the signal fall back to the full calculation, while those
+        // in the second half must agree with it.
+        for &offset in &[0, 5, 15, 16, 24] {
+            assert_eq!(
+                flawed_frequency_transmission_with_offset(signal.clone(), 100, offset),
+                flawed_frequency_transmission(signal.clone(), 100)[offset..offset + 8].to_vec()
+            );
+        }
+    }
+
     #[test]
     fn test_real_signal() {
         let result = part_two(vec![
@@ -1,11 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::io;
 
 extern crate intcode;
 use intcode::{Program, ProgramState};
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 enum Direction {
     Up,
     Down,
@@ -13,6 +13,38 @@ enum Direction {
     Right,
 }
 
+impl Direction {
+    /// Returns the direction faced after turning 90 degrees to the left.
+    fn turn_left(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+        }
+    }
+
+    /// Returns the direction faced after turning 90 degrees to the right.
+    fn turn_right(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// Returns the position one step away from `(x, y)` in this direction.
+    fn travel(self, (x, y): (i64, i64)) -> (i64, i64) {
+        match self {
+            Direction::Up => (x, y - 1),
+            Direction::Down => (x, y + 1),
+            Direction::Left => (x - 1, y),
+            Direction::Right => (x + 1, y),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq)]
 enum TileType {
     Empty,
@@ -43,6 +75,92 @@ impl Canvas {
         Canvas(HashMap::new())
     }
 
+    /// Runs the ASCII program, building a canvas from the camera output.
+    fn from_program(program: Program) -> Canvas {
+        let mut program = program;
+        let mut map = Canvas::new();
+        let mut x = 0;
+        let mut y = 0;
+
+        while let ProgramState::Output(value) = program.run().unwrap() {
+            match value {
+                10 => {
+                    x = -1;
+                    y += 1;
+                }
+                _ => {
+                    map.0.insert((x, y), TileType::from(value as usize));
+                }
+            }
+
+            x += 1;
+        }
+
+        map
+    }
+
+    /// Returns if the robot may travel to the given position.
+    fn is_scaffold(&self, position: (i64, i64)) -> bool {
+        matches!(
+            self.0.get(&position),
+            Some(TileType::Scaffold) | Some(TileType::Robot(_))
+        )
+    }
+
+    /// Walks the robot from its starting position along the scaffold -- continuing straight ahead
+    /// through intersections -- until it reaches the end, or until it returns to a position it
+    /// already passed through in the same direction on a scaffold which forms a closed loop.
+    /// Returns the path as turns and the number of steps taken after each, e.g.
+    /// `["R", "10", "L", "4"]`.
+    fn traversal_path(&self) -> Vec<String> {
+        let (mut position, mut direction) = self
+            .0
+            .iter()
+            .find_map(|(&position, tile)| match tile {
+                TileType::Robot(direction) => Some((position, *direction)),
+                _ => None,
+            })
+            .expect("Expected the map to contain the robot");
+
+        let mut path = Vec::new();
+        let mut visited = HashSet::new();
+
+        visited.insert((position, direction));
+
+        loop {
+            if self.is_scaffold(direction.travel(position)) {
+                let mut steps = 0;
+                let mut looped = false;
+
+                while self.is_scaffold(direction.travel(position)) {
+                    position = direction.travel(position);
+                    steps += 1;
+
+                    if !visited.insert((position, direction)) {
+                        looped = true;
+                        break;
+                    }
+                }
+
+                path.push(steps.to_string());
+
+                if looped {
+                    break;
+                }
+            } else if self.is_scaffold(direction.turn_left().travel(position)) {
+                direction = direction.turn_left();
+                path.push("L".to_string());
+            } else if self.is_scaffold(direction.turn_right().travel(position)) {
+                direction = direction.turn_right();
+                path.push("R".to_string());
+            } else {
+                break;
+            }
+        }
+
+        path
+    }
+
     fn intersections(&self) -> Vec<(i64, i64)> {
         let scaffolds = self
             .0
@@ -106,26 +224,7 @@ impl fmt::Display for Canvas {
     }
 }
 
-fn part_one(program: Program) -> i64 {
-    let mut program = program;
-    let mut map = Canvas::new();
-    let mut x = 0;
-    let mut y = 0;
-
-    while let ProgramState::Output(value) = program.run() {
-        match value {
-            10 => {
-                x = -1;
-                y += 1;
-            }
-            _ => {
-                map.0.insert((x, y), TileType::from(value as usize));
-            }
-        }
-
-        x += 1;
-    }
-
+fn part_one(map: &Canvas) -> i64 {
     let mut sum = 0;
 
     for (x, y) in map.intersections() {
@@ -135,40 +234,249 @@ fn part_one(program: Program) -> i64 {
     sum
 }
 
-fn part_two(program: Program) -> i64 {
-    let mut program = program;
+/// The maximum number of characters in the main routine and in each movement function, excluding
+/// the newline.
+const MAX_ROUTINE_LENGTH: usize = 20;
+
+/// Names of the movement functions, as used in the main routine.
+const FUNCTION_NAMES: [&str; 3] = ["A", "B", "C"];
+
+/// Compresses a path made of turns and move counts -- such as `["R", "8", "L", "10"]` -- into a
+/// main routine and three movement functions A, B, and C, returned in that order as the strings to
+/// be given to the robot. Returns None if the path can't be expressed in routines no longer than
+/// 20 characters.
+fn compress_path(path: &[String]) -> Option<Vec<String>> {
+    // Each turn is joined with the number of steps which follows it; keeping the pairs together
+    // means a function can never start part-way through a move. The path may begin with steps
+    // taken before any turn, which are then a move of their own.
+    let mut moves: Vec<String> = Vec::new();
+
+    for token in path {
+        match moves.last_mut() {
+            Some(last) if (last == "L" || last == "R") && token.parse::<usize>().is_ok() => {
+                last.push(',');
+                last.push_str(token);
+            }
+            _ => moves.push(token.clone()),
+        }
+    }
+
+    let mut functions = Vec::new();
+    let mut calls = Vec::new();
+
+    if moves.is_empty() || !compress_moves(&moves, &mut functions, &mut calls) {
+        return None;
+    }
 
-    // Segments solved by hand.
-    let sequence = "A,C,A,B,A,C,B,C,B,C\n";
-    let movement = "R,10,R,10,R,6,R,4\nR,4,L,4,L,10,L,10\nR,10,R,10,L,4\n";
+    // The robot expects all three functions even if fewer were needed.
+    while functions.len() < FUNCTION_NAMES.len() {
+        functions.push(functions[0]);
+    }
+
+    let main = calls
+        .iter()
+        .map(|&index| FUNCTION_NAMES[index])
+        .collect::<Vec<&str>>()
+        .join(",");
+
+    let mut routines = vec![main];
+    routines.extend(functions.iter().map(|function| function.join(",")));
 
-    for character in sequence.chars() {
-        program.push_input(character as i64);
+    Some(routines)
+}
+
+/// Searches depth-first for a sequence of `calls` to at most three `functions` which together
+/// produce the remaining `moves`. Returns if one was found, in which case `functions` and `calls`
+/// contain the solution.
+fn compress_moves<'a>(
+    moves: &'a [String],
+    functions: &mut Vec<&'a [String]>,
+    calls: &mut Vec<usize>,
+) -> bool {
+    if moves.is_empty() {
+        return true;
     }
 
-    for character in movement.chars() {
-        program.push_input(character as i64);
+    // Each call in the main routine is a name and a comma.
+    if (calls.len() + 1) * 2 - 1 > MAX_ROUTINE_LENGTH {
+        return false;
+    }
+
+    // Try continuing with a function we already have...
+    for index in 0..functions.len() {
+        let function = functions[index];
+
+        if moves.starts_with(function) {
+            calls.push(index);
+
+            if compress_moves(&moves[function.len()..], functions, calls) {
+                return true;
+            }
+
+            calls.pop();
+        }
+    }
+
+    // ... otherwise start a new function here, trying each length which fits.
+    if functions.len() < FUNCTION_NAMES.len() {
+        for length in 1..=moves.len() {
+            let function = &moves[..length];
+
+            if function.join(",").len() > MAX_ROUTINE_LENGTH {
+                break;
+            }
+
+            functions.push(function);
+            calls.push(functions.len() - 1);
+
+            if compress_moves(&moves[length..], functions, calls) {
+                return true;
+            }
+
+            calls.pop();
+            functions.pop();
+        }
+    }
+
+    false
+}
+
+fn part_two(program: Program, path: &[String]) -> i64 {
+    let mut program = program;
+
+    let routines = compress_path(path).expect("Expected the path to be compressible");
+
+    for routine in routines {
+        program.push_ascii(&routine);
     }
 
     // No video output.
-    program.push_input('n' as i64);
-    program.push_input('\n' as i64);
+    program.push_ascii("n");
 
     program
-        .into_iter()
-        .last()
+        .run_ascii()
+        .unwrap()
+        .1
         .expect("Expected robot to return dust quantity")
 }
 
 fn main() -> Result<(), io::Error> {
-    let program = Program::from_file("data/intcodes.txt")?;
-    println!("Part one: {}", part_one(program));
+    let mut program = Program::from_file("data/intcodes.txt")?;
+    let map = Canvas::from_program(program.clone());
 
-    let mut intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
-    intcodes[0] = 2;
-    let program = Program::new(intcodes);
+    println!("Part one: {}", part_one(&map));
 
-    println!("Part two: {}", part_two(program));
+    program.poke(0, 2);
+
+    println!("Part two: {}", part_two(program, &map.traversal_path()));
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expands the main routine and functions produced by `compress_path` back into a path.
+    fn expand_routines(routines: &[String]) -> Vec<String> {
+        routines[0]
+            .split(',')
+            .flat_map(|name| {
+                let index = FUNCTION_NAMES.iter().position(|&n| n == name).unwrap();
+                routines[index + 1].split(',')
+            })
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_traversal_path() {
+        let mut map = Canvas::new();
+
+        let input = "\
+            ..#####\n\
+            ..#...#\n\
+            ^######\n\
+            ..#....\n\
+            ..#....";
+
+        for (y, line) in input.lines().enumerate() {
+            for (x, tile) in line.chars().enumerate() {
+                map.0
+                    .insert((x as i64, y as i64), TileType::from(tile as usize));
+            }
+        }
+
+        assert_eq!(
+            map.traversal_path(),
+            vec!["R", "6", "L", "2", "L", "4", "L", "4"]
+        );
+    }
+
+    #[test]
+    fn test_traversal_path_closed_loop() {
+        let mut map = Canvas::new();
+
+        let input = "\
+            #####\n\
+            #...#\n\
+            ^...#\n\
+            #####";
+
+        for (y, line) in input.lines().enumerate() {
+            for (x, tile) in line.chars().enumerate() {
+                map.0
+                    .insert((x as i64, y as i64), TileType::from(tile as usize));
+            }
+        }
+
+        // One lap of the loop, ending back where the robot started.
+        assert_eq!(
+            map.traversal_path(),
+            vec!["2", "R", "4", "R", "3", "R", "4", "R", "1"]
+        );
+    }
+
+    #[test]
+    fn test_compress_path() {
+        let path = "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2"
+            .split(',')
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        let routines = compress_path(&path).unwrap();
+
+        assert_eq!(routines.len(), 4);
+        assert!(routines.iter().all(|r| r.len() <= MAX_ROUTINE_LENGTH));
+        assert_eq!(expand_routines(&routines), path);
+    }
+
+    #[test]
+    fn test_compress_path_starting_with_steps() {
+        let path = "4,R,8,R,8,R,8"
+            .split(',')
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        let routines = compress_path(&path).unwrap();
+
+        assert_eq!(expand_routines(&routines), path);
+
+        // Functions are made of whole moves: each starts with a turn, apart from the steps taken
+        // before the first turn.
+        assert!(routines[1..]
+            .iter()
+            .all(|function| function.starts_with('R') || function.starts_with('4')));
+    }
+
+    #[test]
+    fn test_compress_path_too_long() {
+        // Each function fits at most four of these moves, so thirteen different moves can't be
+        // expressed with three functions.
+        let path = (10..=22)
+            .flat_map(|steps| vec!["L".to_string(), steps.to_string()])
+            .collect::<Vec<String>>();
+
+        assert_eq!(compress_path(&path), None);
+    }
+}
@@ -1,10 +1,9 @@
 //! The travelling salesman says hi?
 //!
 //! I spent a long time trying to figure out a "correct" solution to this, before seeing that most
-//! on /r/adventofcode treated it as the travelling saleman problem. It may be possible to optimise
-//! by calculating the path from each key to each other key only once, and keeping track of which
-//! doors are blocking the path before determing if the path is valid or not. I haven't done that
-//! since running with --release presents an answer within a couple of minutes.
+//! on /r/adventofcode treated it as the travelling saleman problem. The path from each start and
+//! key to each other key is calculated only once, keeping track of the doors -- and other keys --
+//! along the way. A path may then be taken only once we have all the keys it requires.
 //!
 //! See `minimum_steps` for the main calculation.
 
@@ -43,15 +42,6 @@ impl Pos {
     }
 }
 
-/// Describes a path through the Map from a starting position (not contained in the struct). The
-/// Path contains the distance from the start to the `end_position`, and the ID of which robot can
-/// traverse the path (always 0 in part 1, 0-3 in part 2).
-struct Path {
-    distance: u32,
-    end_position: Pos,
-    robot_id: usize,
-}
-
 struct Map {
     inner: HashMap<Pos, TileType>,
     starts: Vec<Pos>,
@@ -66,104 +56,55 @@ impl Map {
         }
     }
 
-    /// Returns a HashMap where each key is the ID of a reachable key in the map, and each value is
-    /// a tuple containing the distance from the start position, and the position.
-    fn reachable_keys(&self, start: Pos, have: &CharMaskSet) -> HashMap<char, Path> {
-        let mut reachable = HashMap::new();
+    /// Returns the ID and position of each key in the map.
+    fn keys(&self) -> Vec<(char, Pos)> {
+        self.inner
+            .iter()
+            .filter_map(|(pos, tile)| match tile {
+                TileType::Key(character) => Some((*character, *pos)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns a HashMap where each key is the ID of a key reachable from the start position, and
+    /// each value is a tuple containing the distance to the key and the keys required to get
+    /// there: those for the doors along the path, and any other keys the path passes through.
+    fn paths_from(&self, start: Pos) -> HashMap<char, (u32, CharMaskSet)> {
+        let mut paths = HashMap::new();
         let mut visited = HashSet::new();
         let mut queue = VecDeque::new();
-        let mut distance: u32 = 1;
 
-        queue.push_back(start);
+        visited.insert(start);
+        queue.push_back((start, 0, CharMaskSet::new()));
 
-        while queue.len() != 0 {
-            let mut new_queue = VecDeque::new();
+        while let Some((pos, distance, required)) = queue.pop_front() {
+            for neighbor in pos.visitable_neighbors(self) {
+                // We've been here.
+                if !visited.insert(neighbor) {
+                    continue;
+                }
 
-            while let Some(pos) = queue.pop_front() {
-                for neighbor in pos.visitable_neighbors(self) {
-                    // We've been here.
-                    if visited.contains(&neighbor) {
-                        continue;
-                    }
+                let mut required = required;
 
-                    // Ignore if wall.
-                    if let Some(TileType::Wall) = self.inner.get(&neighbor) {
-                        continue;
-                    }
+                match self.inner.get(&neighbor) {
+                    Some(TileType::Key(character)) => {
+                        paths.insert(*character, (distance + 1, required));
 
-                    // Ignore if door for which we don't have a key.
-                    if let Some(TileType::Door(character)) = self.inner.get(&neighbor) {
-                        if !have.contains(&character.to_ascii_lowercase()) {
-                            continue;
-                        }
+                        // Any key further along this path is only reached after collecting this.
+                        required.insert(*character);
                     }
-
-                    visited.insert(pos);
-
-                    if let Some(TileType::Key(character)) = self.inner.get(&neighbor) {
-                        if have.contains(character) {
-                            // Already have this key: keep going.
-                            new_queue.push_back(neighbor);
-                        } else {
-                            // If we collected a key, add it. No point in traversing further as any
-                            // other keys we find will be more distant on this path than this one.
-                            reachable.insert(
-                                *character,
-                                Path {
-                                    distance: distance,
-                                    end_position: neighbor,
-                                    robot_id: 0,
-                                },
-                            );
-                        }
-                    } else {
-                        // Otherwise keep traversing.
-                        new_queue.push_back(neighbor);
+                    Some(TileType::Door(character)) => {
+                        required.insert(character.to_ascii_lowercase());
                     }
+                    _ => {}
                 }
-            }
-
-            queue = new_queue;
-            distance += 1;
-        }
 
-        reachable
-    }
-
-    /// Computes the reachable keys from each start positions.
-    ///
-    /// The returned HashMap contains characters as the keys, and a tuple of containing the distance
-    /// to the key, the Pos(ition) of the key, and the the start position index from which the key
-    /// is reachable.
-    fn reachable_keys_multiple(
-        &self,
-        starts: &Vec<Pos>,
-        have: &CharMaskSet,
-    ) -> HashMap<char, Path> {
-        let mut keys = HashMap::new();
-
-        for (index, start) in starts.iter().enumerate() {
-            for (
-                character,
-                Path {
-                    distance,
-                    end_position,
-                    ..
-                },
-            ) in self.reachable_keys(*start, have)
-            {
-                keys.insert(
-                    character,
-                    Path {
-                        distance,
-                        end_position,
-                        robot_id: index,
-                    },
-                );
+                queue.push_back((neighbor, distance + 1, required));
             }
         }
 
-        keys
+        paths
     }
 }
 
@@ -195,8 +136,11 @@ impl From<String> for Map {
 }
 
 /// Describes which keys we already have.
+///
+/// Each character is a bit in a u64, so the set can never hold more than 64 distinct characters. In
+/// practice it holds up to 62: the lowercase and uppercase ASCII letters, and the digits.
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
-struct CharMaskSet(u32);
+struct CharMaskSet(u64);
 
 impl CharMaskSet {
     fn new() -> CharMaskSet {
@@ -207,8 +151,13 @@ impl CharMaskSet {
         self.0 & CharMaskSet::char_to_mask(*character) != 0
     }
 
+    /// Returns if every character in `other` is also in this set.
+    fn is_superset(&self, other: &CharMaskSet) -> bool {
+        self.0 & other.0 == other.0
+    }
+
     fn insert(&mut self, character: char) {
-        self.0 += CharMaskSet::char_to_mask(character);
+        self.0 |= CharMaskSet::char_to_mask(character);
     }
 
     /// Creates a clone of the CharMaskSet and adds the `character` to the new CharMaskSet.
@@ -220,103 +169,172 @@ impl CharMaskSet {
     }
 
     #[inline(always)]
-    fn char_to_mask(character: char) -> u32 {
-        if !character.is_ascii_lowercase() {
-            panic!(
-                "CharMaskSet may only contain lowercase ASCII. Got: {}",
+    fn char_to_mask(character: char) -> u64 {
+        let bit = match character {
+            'a'..='z' => character as u8 - b'a',
+            'A'..='Z' => character as u8 - b'A' + 26,
+            '0'..='9' => character as u8 - b'0' + 52,
+            _ => panic!(
+                "CharMaskSet may only contain ASCII letters and digits. Got: {}",
                 character
-            );
-        }
+            ),
+        };
 
-        1 << (character as u8 - 'a' as u8)
+        1 << bit
     }
 }
 
 impl From<&Vec<char>> for CharMaskSet {
-    // Assumes that all characters are lowercase ASCII.
+    // Assumes that all characters are ASCII letters or digits.
     fn from(characters: &Vec<char>) -> CharMaskSet {
         let mut mask = 0;
 
         for character in characters {
-            mask += CharMaskSet::char_to_mask(*character);
+            mask |= CharMaskSet::char_to_mask(*character);
         }
 
         CharMaskSet(mask)
     }
 }
 
-/// Given a vector of start positions, returns a suitable hash key to represent them.
-///
-/// This feels terribly ugly, but neatly works around &Vec<Pos> not being hashable.
-fn starts_key(starts: &Vec<Pos>) -> String {
-    starts
+/// The distance between each pair of locations -- a start or a key -- and the keys required to
+/// travel between them. Starts are identified by their index as a digit: '0', '1', etc.
+type KeyDistances = HashMap<(char, char), (u32, CharMaskSet)>;
+
+/// Returns the ID used for a start position in `KeyDistances`.
+fn start_id(index: usize) -> char {
+    std::char::from_digit(index as u32, 10).expect("Expected no more than ten start positions")
+}
+
+/// Calculates the distances from each start, and each key, to every key reachable from it.
+fn key_distances(map: &Map, keys: &[(char, Pos)], starts: &[Pos]) -> KeyDistances {
+    let mut distances = HashMap::new();
+
+    let starts = starts
         .iter()
-        .map(|start| format!("{:?}", start))
-        .collect::<Vec<String>>()
-        .join("")
+        .enumerate()
+        .map(|(index, start)| (start_id(index), *start));
+
+    for (from, pos) in starts.chain(keys.iter().cloned()) {
+        for (to, path) in map.paths_from(pos) {
+            distances.insert((from, to), path);
+        }
+    }
+
+    distances
 }
 
+/// Maps robot locations and the keys already collected to the minimum number of steps needed to
+/// collect the remaining keys, and the first move -- the ID of the robot, and the key it collects --
+/// on the way to doing so.
+type SeenStates = HashMap<(Vec<char>, CharMaskSet), (u32, Option<(usize, char)>)>;
+
 /// Calculate the minimum steps to collect all keys.
 ///
-/// map - The parsed map.
-/// starts - A vector of positions where a robot is located.
+/// distances - The distances between each start and key in the map.
+/// keys - The IDs of every key in the map.
+/// robots - The location of each robot: either a start or the ID of the last key it collected.
 /// have - A CharMaskSet containing the keys already collected.
-/// seen - A cache of starting positions and collected keys to reduce the number of calculations.
+/// seen - A cache of robot locations and collected keys to reduce the number of calculations.
 fn minimum_steps(
-    map: &Map,
-    starts: &Vec<Pos>,
+    distances: &KeyDistances,
+    keys: &[char],
+    robots: &[char],
     have: CharMaskSet,
-    seen: &mut HashMap<(String, CharMaskSet), u32>,
+    seen: &mut SeenStates,
 ) -> u32 {
-    let cache_key = (starts_key(starts), have);
+    let cache_key = (robots.to_vec(), have);
 
-    if let Some(steps) = seen.get(&cache_key) {
+    if let Some((steps, _)) = seen.get(&cache_key) {
         return *steps;
     }
 
-    let keys = map.reachable_keys_multiple(starts, &have);
+    let mut min_steps: Option<(u32, (usize, char))> = None;
 
-    if keys.len() == 0 {
-        // All keys are collected when there area no reachable keys
-        return 0;
-    }
+    for (robot_id, robot) in robots.iter().enumerate() {
+        for &key in keys {
+            if have.contains(&key) {
+                continue;
+            }
 
-    let mut min_steps = u32::max_value();
+            // Keys which the robot can't reach at all, or can't reach without first collecting
+            // others, aren't candidates.
+            let distance = match distances.get(&(*robot, key)) {
+                Some((distance, required)) if have.is_superset(required) => *distance,
+                _ => continue,
+            };
 
-    for (character, path) in keys {
-        let new_starts = starts
-            .iter()
-            .enumerate()
-            .map(|(index, p)| {
-                // Only move the robot we're currently calculating (path.robot_id) to the end of the
-                // current path; leave the others at their current position.
-                if index == path.robot_id {
-                    path.end_position
-                } else {
-                    *p
-                }
-            })
-            .collect::<Vec<Pos>>();
+            // Move only the robot which collects the key; leave the others where they are.
+            let mut new_robots = robots.to_vec();
+            new_robots[robot_id] = key;
 
-        let distance =
-            path.distance + minimum_steps(&map, &new_starts, have.clone_insert(character), seen);
+            let distance = distance
+                + minimum_steps(distances, keys, &new_robots, have.clone_insert(key), seen);
 
-        if distance < min_steps {
-            min_steps = distance;
+            match min_steps {
+                Some((min, _)) if min <= distance => {}
+                _ => min_steps = Some((distance, (robot_id, key))),
+            }
         }
     }
 
-    // `seen` keeps track of start positions and the keys already collected, and maps them to the
-    // minimum number of steps.
-    seen.insert(cache_key.clone(), min_steps);
+    // All keys are collected when there are no reachable keys.
+    let (steps, next_move) = match min_steps {
+        Some((steps, next_move)) => (steps, Some(next_move)),
+        None => (0, None),
+    };
+
+    seen.insert(cache_key, (steps, next_move));
+
+    steps
+}
+
+/// Follows the best moves recorded in `seen` from the given robot locations, returning the order
+/// in which the keys are collected.
+fn collection_order(seen: &SeenStates, robots: &[char], have: CharMaskSet) -> Vec<char> {
+    let mut robots = robots.to_vec();
+    let mut have = have;
+    let mut order = Vec::new();
+
+    while let Some((_, Some((robot_id, key)))) = seen.get(&(robots.clone(), have)) {
+        robots[*robot_id] = *key;
+        have.insert(*key);
+        order.push(*key);
+    }
+
+    order
+}
+
+/// Computes the shortest path for robots at each of the `starts` to collect all keys, returning
+/// the number of steps and the order in which keys are collected.
+fn shortest_path_from(map: &Map, starts: &[Pos]) -> (u32, Vec<char>) {
+    let keys = map.keys();
+    let distances = key_distances(map, &keys, starts);
+
+    let keys = keys.iter().map(|(key, _)| *key).collect::<Vec<char>>();
+    let robots = (0..starts.len()).map(start_id).collect::<Vec<char>>();
+
+    let mut seen = HashMap::new();
+    let steps = minimum_steps(&distances, &keys, &robots, CharMaskSet::new(), &mut seen);
 
-    min_steps
+    (steps, collection_order(&seen, &robots, CharMaskSet::new()))
 }
 
 /// Computes the shortest path to collect all keys.
 fn shortest_path(map: Map) -> u32 {
-    let mut seen = HashMap::new();
-    minimum_steps(&map, &map.starts, CharMaskSet::new(), &mut seen)
+    shortest_path_with_order(map).0
+}
+
+/// Computes the shortest path to collect all keys, returning the number of steps and the order in
+/// which the keys are collected.
+fn shortest_path_with_order(map: Map) -> (u32, Vec<char>) {
+    shortest_path_from(&map, &map.starts)
+}
+
+/// Computes the shortest path for four robots, starting at the given positions, to collect all keys.
+/// Any start positions contained in the map itself are ignored.
+fn shortest_path_four(map: Map, starts: [Pos; 4]) -> u32 {
+    shortest_path_from(&map, &starts).0
 }
 
 fn main() -> Result<(), io::Error> {
@@ -327,8 +345,9 @@ fn main() -> Result<(), io::Error> {
 
     let map = fs::read_to_string("data/map.p2.txt")?;
     let map = Map::from(map);
+    let starts = [map.starts[0], map.starts[1], map.starts[2], map.starts[3]];
 
-    println!("Part two: {:?}", shortest_path(map));
+    println!("Part two: {:?}", shortest_path_four(map, starts));
 
     Ok(())
 }
@@ -366,6 +385,40 @@ mod tests {
         assert_eq!(shortest_path(map), 86);
     }
 
+    #[test]
+    fn test_part_one_second_example_order() {
+        let map = Map::from(trim_leading_whitespace(
+            "########################
+             #f.D.E.e.C.b.A.@.a.B.c.#
+             ######################.#
+             #d.....................#
+             ########################",
+        ));
+
+        let distances = key_distances(&map, &map.keys(), &map.starts);
+        let (steps, order) = shortest_path_with_order(map);
+
+        assert_eq!(steps, 86);
+        assert_eq!(order, vec!['a', 'b', 'c', 'd', 'e', 'f']);
+
+        // Following the order collects every key in the reported number of steps.
+        let mut have = CharMaskSet::new();
+        let mut location = start_id(0);
+        let mut total = 0;
+
+        for key in order {
+            let (distance, required) = distances[&(location, key)];
+
+            assert!(have.is_superset(&required));
+
+            have.insert(key);
+            location = key;
+            total += distance;
+        }
+
+        assert_eq!(total, 86);
+    }
+
     #[test]
     fn test_part_one_third_example() {
         let map = Map::from(trim_leading_whitespace(
@@ -410,7 +463,6 @@ mod tests {
         assert_eq!(shortest_path(map), 81);
     }
 
-    // Test fails. All others, including the real problem, pass fine. :|
     #[test]
     fn test_part_two_first_example() {
         let map = Map::from(trim_leading_whitespace(
@@ -426,6 +478,42 @@ mod tests {
         assert_eq!(shortest_path(map), 8);
     }
 
+    #[test]
+    fn test_part_two_first_example_key_distances() {
+        let map = Map::from(trim_leading_whitespace(
+            "#######
+              #a.#Cd#
+              ##@#@##
+              #######
+              ##@#@##
+              #cB#Ab#
+              #######",
+        ));
+
+        let distances = key_distances(&map, &map.keys(), &map.starts);
+
+        // Each robot can reach only the key in its own quadrant.
+        for (start, key, required) in &[
+            ('0', 'a', None),
+            ('1', 'd', Some('c')),
+            ('2', 'c', Some('b')),
+            ('3', 'b', Some('a')),
+        ] {
+            let required = required.map(|c| vec![c]).unwrap_or_default();
+
+            for other in &['a', 'b', 'c', 'd'] {
+                if other == key {
+                    assert_eq!(
+                        distances.get(&(*start, *other)),
+                        Some(&(2, CharMaskSet::from(&required)))
+                    );
+                } else {
+                    assert_eq!(distances.get(&(*start, *other)), None);
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_part_two_second_example() {
         let map = Map::from(trim_leading_whitespace(
@@ -456,6 +544,26 @@ mod tests {
         assert_eq!(shortest_path(map), 32);
     }
 
+    #[test]
+    fn test_part_two_explicit_starts() {
+        let map = Map::from(trim_leading_whitespace(
+            "#############
+             #DcBa.#.GhKl#
+             #.###.#.#I###
+             #e#d#####j#k#
+             ###C#.#.###J#
+             #fEbA.#.FgHi#
+             #############",
+        ));
+
+        assert!(map.starts.is_empty());
+
+        assert_eq!(
+            shortest_path_four(map, [Pos(5, 2), Pos(7, 2), Pos(5, 4), Pos(7, 4)]),
+            32
+        );
+    }
+
     #[test]
     fn test_part_two_fourth_example() {
         let map = Map::from(trim_leading_whitespace(
@@ -473,6 +581,17 @@ mod tests {
         assert_eq!(shortest_path(map), 72);
     }
 
+    #[test]
+    fn test_puzzle_input() -> Result<(), io::Error> {
+        let map = Map::from(fs::read_to_string("data/map.p1.txt")?);
+        assert_eq!(shortest_path(map), 4248);
+
+        let map = Map::from(fs::read_to_string("data/map.p2.txt")?);
+        assert_eq!(shortest_path(map), 1878);
+
+        Ok(())
+    }
+
     #[test]
     fn test_char_mask_set() {
         let mut set = CharMaskSet::from(&vec!['a', 'c', 'd']);
@@ -486,11 +605,65 @@ mod tests {
         set.insert('e');
         assert!(set.contains(&'e'));
         assert!(!set.contains(&'b'));
+
+        assert!(set.is_superset(&CharMaskSet::from(&vec!['a', 'e'])));
+        assert!(set.is_superset(&CharMaskSet::new()));
+        assert!(!set.is_superset(&CharMaskSet::from(&vec!['a', 'b'])));
+    }
+
+    #[test]
+    fn test_key_distances() {
+        let map = Map::from(trim_leading_whitespace(
+            "#########
+             #b.A.@.a#
+             #########",
+        ));
+
+        let distances = key_distances(&map, &map.keys(), &map.starts);
+
+        assert_eq!(distances.get(&('0', 'a')), Some(&(2, CharMaskSet::new())));
+        assert_eq!(
+            distances.get(&('0', 'b')),
+            Some(&(4, CharMaskSet::from(&vec!['a'])))
+        );
+        assert_eq!(
+            distances.get(&('a', 'b')),
+            Some(&(6, CharMaskSet::from(&vec!['a'])))
+        );
+    }
+
+    #[test]
+    fn test_char_mask_set_duplicates() {
+        let mut set = CharMaskSet::new();
+
+        set.insert('a');
+        set.insert('a');
+
+        assert!(set.contains(&'a'));
+        assert!(!set.contains(&'b'));
+
+        let set = CharMaskSet::from(&vec!['a', 'a']);
+
+        assert!(set.contains(&'a'));
+        assert!(!set.contains(&'b'));
+    }
+
+    #[test]
+    fn test_char_mask_set_beyond_lowercase() {
+        let set = CharMaskSet::from(&vec!['z', 'A', 'Z', '0', '9']);
+
+        for character in &['z', 'A', 'Z', '0', '9'] {
+            assert!(set.contains(character));
+        }
+
+        for character in &['a', 'y', 'B', 'Y', '1', '8'] {
+            assert!(!set.contains(character));
+        }
     }
 
     #[test]
-    #[should_panic(expected = "CharMaskSet may only contain lowercase ASCII. Got: A")]
-    fn test_char_set_mask_uppercase() {
-        CharMaskSet::from(&vec!['A']);
+    #[should_panic(expected = "CharMaskSet may only contain ASCII letters and digits. Got: @")]
+    fn test_char_set_mask_invalid() {
+        CharMaskSet::new().insert('@');
     }
 }
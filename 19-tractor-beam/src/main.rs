@@ -3,12 +3,12 @@ use std::io;
 extern crate intcode;
 use intcode::{Program, ProgramState};
 
-fn part_one(intcodes: Vec<i64>) -> usize {
+fn part_one(program: &Program) -> usize {
     let mut beam = 0;
 
     for y in 0..50 {
         for x in 0..50 {
-            if is_inside_beam(intcodes.clone(), x, y) {
+            if is_inside_beam(program, x, y) {
                 beam += 1;
             }
         }
@@ -17,47 +17,151 @@ fn part_one(intcodes: Vec<i64>) -> usize {
     beam
 }
 
-fn is_inside_beam(intcodes: Vec<i64>, x: i64, y: i64) -> bool {
-    let mut program = Program::new(intcodes);
+/// Deploys a drone to the given position, returning if it is pulled by the beam. The drone program
+/// can only be used once, so a copy of `program` is run.
+fn is_inside_beam(program: &Program, x: i64, y: i64) -> bool {
+    let mut program = program.clone();
 
-    program.push_input(x);
-    program.push_input(y);
+    program.push_inputs(&[x, y]);
 
-    match program.run() {
+    match program.run().unwrap() {
         ProgramState::Output(0) => false,
         ProgramState::Output(1) => true,
         _ => unreachable!(),
     }
 }
 
-fn part_two(intcodes: Vec<i64>) -> i64 {
-    // The beam spreads out (somewhat) diagonally. If we're not in the beam at a particular point
-    // then we're not far enough to the right.
-    let mut x = 0;
+/// Returns the largest X worth scanning for the beam on row `y`. The beam can't be more than a few
+/// times wider than it is far from the emitter; this bound prevents scanning forever along a row
+/// which contains no beam, as happens on some of the rows closest to the emitter.
+fn scan_limit(y: i64) -> i64 {
+    y * 10
+}
+
+/// Approximates the slopes (y / x) of the two edges of the beam by finding where it begins and ends
+/// on the row `probe_distance` away from the emitter. The first value is the slope of the lower
+/// (right-hand) edge, the second that of the upper (left-hand) edge.
+///
+/// The further away the probe, the more accurate the slopes; the beam is pixelated close to the
+/// emitter and may not be present at all on some of the first few rows.
+///
+/// Not used to solve either part; it is kept to sanity-check the beam's shape.
+#[allow(dead_code)]
+fn beam_slopes(program: &Program, probe_distance: i64) -> (f64, f64) {
+    let y = probe_distance;
+
+    let mut xs = (0..=scan_limit(y)).skip_while(|&x| !is_inside_beam(program, x, y));
 
-    // The beam isn't wide enough in the first 100 positions.
-    let mut y = 100;
+    let first_x = xs
+        .next()
+        .expect("Expected the beam to be present on the probed row");
 
-    loop {
-        // Check (hopefully) the bottom left position of the tractor beam.
-        if is_inside_beam(intcodes.clone(), x, y) {
-            // Check 100 positions to the right and 100 positions up.
-            if is_inside_beam(intcodes.clone(), x + 99, y - 99) {
-                return x * 10000 + y - 99;
+    let last_x = xs
+        .take_while(|&x| is_inside_beam(program, x, y))
+        .last()
+        .unwrap_or(first_x);
+
+    (y as f64 / last_x as f64, y as f64 / first_x as f64)
+}
+
+/// Finds the square of the given `size` closest to the emitter which fits entirely within the beam,
+/// returning the position of its top-left corner.
+fn closest_square(program: &Program, size: i64) -> (i64, i64) {
+    // The beam spreads out (somewhat) diagonally, so both its edges move right as y increases. Each
+    // row is scanned starting from the edges found on the row above.
+    let mut left = 0;
+    let mut right = 0;
+
+    // The right-hand edge of the beam on each row scanned so far, or None where the beam is absent.
+    let mut right_edges = Vec::new();
+
+    for y in 0.. {
+        let x = match (left..=scan_limit(y)).find(|&x| is_inside_beam(program, x, y)) {
+            Some(x) => x,
+            None => {
+                right_edges.push(None);
+                continue;
             }
-        } else {
-            x += 1;
+        };
+
+        left = x;
+
+        if right < left || !is_inside_beam(program, right, y) {
+            right = left;
+        }
+
+        while is_inside_beam(program, right + 1, y) {
+            right += 1;
         }
 
-        y += 1;
+        right_edges.push(Some(right));
+
+        // The square fits with its bottom-left corner here if the beam on the row where the top of
+        // the square would be extends far enough right of this row's edge.
+        let top = y - (size - 1);
+
+        if top >= 0 {
+            if let Some(top_right) = right_edges[top as usize] {
+                if top_right >= left + size - 1 {
+                    return (left, top);
+                }
+            }
+        }
     }
+
+    unreachable!()
+}
+
+/// Finds the 100x100 square closest to the emitter which fits in the beam, returning its top-left
+/// X coordinate multiplied by 10000, plus its Y coordinate.
+fn part_two(program: &Program) -> i64 {
+    let (x, y) = closest_square(program, 100);
+
+    x * 10000 + y
 }
 
 fn main() -> Result<(), io::Error> {
-    let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
+    let program = Program::from_file("data/intcodes.txt")?;
 
-    println!("Part one: {}", part_one(intcodes.clone()));
-    println!("Part two: {}", part_two(intcodes));
+    println!("Part one: {}", part_one(&program));
+    println!("Part two: {}", part_two(&program));
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_two() -> Result<(), io::Error> {
+        let program = Program::from_file("data/intcodes.txt")?;
+
+        assert_eq!(part_two(&program), 10671712);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_closest_square() {
+        // A beam which covers x <= y <= 2x.
+        let program = Program::new(vec![
+            3, 100, 3, 101, 7, 101, 100, 102, 1002, 100, 2, 104, 7, 104, 101, 103, 1, 102, 103,
+            105, 1002, 105, -1, 105, 1001, 105, 1, 105, 4, 105, 99,
+        ]);
+
+        assert_eq!(closest_square(&program, 1), (0, 0));
+        assert_eq!(closest_square(&program, 10), (18, 27));
+    }
+
+    #[test]
+    fn test_beam_slopes() -> Result<(), io::Error> {
+        let program = Program::from_file("data/intcodes.txt")?;
+        let (lower, upper) = beam_slopes(&program, 100);
+
+        assert!(lower > 0.0);
+        assert!(upper > lower);
+
+        Ok(())
+    }
+}
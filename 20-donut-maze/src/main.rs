@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::{fs, io};
+use std::{env, fs, io};
 
 extern crate pathfinding;
 use pathfinding::directed::bfs::bfs;
@@ -81,10 +81,13 @@ fn is_portal_tile(map: &HashMap<Pos, char>, pos: &Pos) -> bool {
     false
 }
 
-/// Determines from the position and map size whether a portal at the position is an inner portal
-/// (which increases the layer level) or an outer portal (which decreases it).
-fn portal_layer_delta(pos: &Pos, map_width: u32, map_height: u32) -> i32 {
-    if pos.0 <= 3 || pos.0 >= map_width - 3 || pos.1 <= 3 || pos.1 >= map_height - 3 {
+/// Determines whether a portal at the position is an inner portal (which increases the layer level)
+/// or an outer portal (which decreases it). Outer portals lie on the edge of the `bounds` of the
+/// maze: the top-left and bottom-right corners of the smallest box containing every open tile.
+fn portal_layer_delta(pos: &Pos, bounds: &(Pos, Pos)) -> i32 {
+    let (min, max) = bounds;
+
+    if pos.0 == min.0 || pos.0 == max.0 || pos.1 == min.1 || pos.1 == max.1 {
         1
     } else {
         -1
@@ -165,8 +168,24 @@ impl From<String> for Map {
             }
         }
 
-        let map_width = intermediate.keys().max_by_key(|Pos(x, _)| x).unwrap().0 + 1;
-        let map_height = intermediate.keys().max_by_key(|Pos(_, y)| y).unwrap().1 + 1;
+        // The margin around the maze containing portal names may be any width, so find where the
+        // open tiles begin and end.
+        let open_tiles = intermediate
+            .iter()
+            .filter(|(_, character)| **character == '.')
+            .map(|(pos, _)| *pos)
+            .collect::<Vec<Pos>>();
+
+        let bounds = (
+            Pos(
+                open_tiles.iter().map(|Pos(x, _)| *x).min().unwrap_or(0),
+                open_tiles.iter().map(|Pos(_, y)| *y).min().unwrap_or(0),
+            ),
+            Pos(
+                open_tiles.iter().map(|Pos(x, _)| *x).max().unwrap_or(0),
+                open_tiles.iter().map(|Pos(_, y)| *y).max().unwrap_or(0),
+            ),
+        );
 
         // For each character in the intermediate map, create an appropriate tiletype in the real
         // map.
@@ -195,7 +214,7 @@ impl From<String> for Map {
                                 empty_pos,
                                 TileType::Portal(
                                     *other_pos,
-                                    portal_layer_delta(other_pos, map_width, map_height),
+                                    portal_layer_delta(other_pos, &bounds),
                                 ),
                             );
 
@@ -203,7 +222,7 @@ impl From<String> for Map {
                                 *other_pos,
                                 TileType::Portal(
                                     empty_pos,
-                                    portal_layer_delta(&empty_pos, map_width, map_height),
+                                    portal_layer_delta(&empty_pos, &bounds),
                                 ),
                             );
                         } else {
@@ -231,48 +250,96 @@ impl From<String> for Map {
     }
 }
 
-/// Calculates the minimum number of steps required to traverse a non-recursive maze.
-fn part_one(map: Map) -> usize {
+/// Finds the shortest path through a non-recursive maze, returning every position visited from the
+/// start to the exit inclusive.
+fn solve_path(map: &Map) -> Vec<Pos> {
     bfs(
         &map.start,
         |&pos| {
-            pos.visitable_neighbors(&map, 0)
+            pos.visitable_neighbors(map, 0)
                 .into_iter()
                 .map(|(pos, _)| pos)
         },
         |pos| pos == &map.exit,
     )
     .expect("Expected to find path to the exit")
-    .len()
-        - 1
 }
 
-/// Calculates the minimum number of steps required to traverse a recursive maze where each "inner"
-/// portal transports the traveller to a copy of the maze one level deeper, and each "outer" portal
-/// returns us one level higher. Only once reaching "ZZ" at layer 0 have we completed the maze.
-fn part_two(map: Map) -> usize {
+/// The deepest layer of a recursive maze which will be searched for a path to the exit.
+const MAX_DEPTH: i32 = 50;
+
+/// Finds the shortest path through a recursive maze, returning every position visited from the
+/// start to the exit inclusive, along with the layer of the maze in which it was visited. Layers
+/// deeper than `max_depth` are not explored; returns None if there is no path within them.
+fn solve_recursive_path(map: &Map, max_depth: i32) -> Option<Vec<(Pos, i32)>> {
     bfs(
         &(map.start, 0),
         |&(pos, layer)| {
-            pos.visitable_neighbors(&map, layer)
+            pos.visitable_neighbors(map, layer)
                 .into_iter()
                 // If we're already at the top maze level, we cannot go through an outer portal as
-                // that would lead to a negative level.
-                .filter(|(_, level)| *level >= 0)
+                // that would lead to a negative level. Without a limit on the depth, a maze with no
+                // path to the exit would be searched forever.
+                .filter(move |(_, level)| *level >= 0 && *level <= max_depth)
         },
         |&(pos, layer)| pos == map.exit && layer == 0,
     )
-    .expect("Expected to find path to the exit")
-    .len()
-        - 1
+}
+
+/// Draws the map with each position in the `path` marked with a `*`. Walls are drawn as `#` and
+/// other visitable tiles as `.`; portal names are not retained by the Map and are left blank.
+fn render_solution(map: &Map, path: &[Pos]) -> String {
+    let max_x = map.inner.keys().map(|Pos(x, _)| *x).max().unwrap_or(0);
+    let max_y = map.inner.keys().map(|Pos(_, y)| *y).max().unwrap_or(0);
+
+    let mut output = String::with_capacity(((max_x + 2) * (max_y + 1)) as usize);
+
+    for y in 0..=max_y {
+        for x in 0..=max_x {
+            let pos = Pos(x, y);
+
+            output.push(match map.inner.get(&pos) {
+                Some(_) if path.contains(&pos) => '*',
+                Some(TileType::Wall) => '#',
+                Some(_) => '.',
+                None => ' ',
+            });
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Calculates the minimum number of steps required to traverse a non-recursive maze.
+fn part_one(map: Map) -> usize {
+    solve_path(&map).len() - 1
+}
+
+/// Calculates the minimum number of steps required to traverse a recursive maze where each "inner"
+/// portal transports the traveller to a copy of the maze one level deeper, and each "outer" portal
+/// returns us one level higher. Only once reaching "ZZ" at layer 0 have we completed the maze.
+///
+/// Returns None if the exit can't be reached without going deeper than `max_depth`.
+fn part_two(map: Map, max_depth: i32) -> Option<usize> {
+    solve_recursive_path(&map, max_depth).map(|path| path.len() - 1)
 }
 
 fn main() -> Result<(), io::Error> {
     let map = Map::from(fs::read_to_string("data/map.txt")?);
+
+    // With the --render flag the maze is printed with the part one path drawn on it.
+    if env::args().skip(1).any(|arg| arg == "--render") {
+        println!("{}", render_solution(&map, &solve_path(&map)));
+    }
+
     println!("Part one: {}", part_one(map));
 
     let map = Map::from(fs::read_to_string("data/map.txt")?);
-    println!("Part two: {}", part_two(map));
+    let steps = part_two(map, MAX_DEPTH).expect("Expected to find path to the exit");
+
+    println!("Part two: {}", steps);
 
     Ok(())
 }
@@ -309,6 +376,87 @@ FG..#########.....#
         assert_eq!(part_one(map), 23);
     }
 
+    #[test]
+    fn test_solve_path() {
+        let map = Map::from(
+            "         A
+         A
+  #######.#########
+  #######.........#
+  #######.#######.#
+  #######.#######.#
+  #######.#######.#
+  #####  B    ###.#
+BC...##  C    ###.#
+  ##.##       ###.#
+  ##...DE  F  ###.#
+  #####    G  ###.#
+  #########.#####.#
+DE..#######...###.#
+  #.#########.###.#
+FG..#########.....#
+  ###########.#####
+             Z
+             Z"
+            .to_string(),
+        );
+
+        let path = solve_path(&map);
+
+        assert_eq!(path.first(), Some(&map.start));
+        assert_eq!(path.last(), Some(&map.exit));
+        assert_eq!(path.len(), 24);
+
+        let path = solve_recursive_path(&map, MAX_DEPTH).unwrap();
+
+        assert_eq!(path.first(), Some(&(map.start, 0)));
+        assert_eq!(path.last(), Some(&(map.exit, 0)));
+        assert_eq!(path.len(), 27);
+
+        // The shortest route through the recursive maze uses no portals, so stays on the
+        // outermost layer.
+        assert!(path.iter().all(|(_, layer)| *layer == 0));
+    }
+
+    #[test]
+    fn test_render_solution() {
+        let map = Map::from(
+            "         A
+         A
+  #######.#########
+  #######.........#
+  #######.#######.#
+  #######.#######.#
+  #######.#######.#
+  #####  B    ###.#
+BC...##  C    ###.#
+  ##.##       ###.#
+  ##...DE  F  ###.#
+  #####    G  ###.#
+  #########.#####.#
+DE..#######...###.#
+  #.#########.###.#
+FG..#########.....#
+  ###########.#####
+             Z
+             Z"
+            .to_string(),
+        );
+
+        let rendered = render_solution(&map, &solve_path(&map));
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        // Start.
+        assert_eq!(lines[2].chars().nth(9), Some('*'));
+
+        // Exit.
+        assert_eq!(lines[16].chars().nth(13), Some('*'));
+
+        assert_eq!(lines[3], "  #######*........#");
+        assert_eq!(lines[4], "  #######*#######.#");
+        assert_eq!(rendered.matches('*').count(), 24);
+    }
+
     #[test]
     fn test_portal_layer_delta() {
         let map = Map::from(
@@ -353,6 +501,50 @@ FG..#########.....#
         )
     }
 
+    #[test]
+    fn test_portal_layer_delta_wide_margin() {
+        // The simple map, with three extra columns of margin on the left.
+        let map = Map::from(
+            "         A
+         A
+  #######.#########
+  #######.........#
+  #######.#######.#
+  #######.#######.#
+  #######.#######.#
+  #####  B    ###.#
+BC...##  C    ###.#
+  ##.##       ###.#
+  ##...DE  F  ###.#
+  #####    G  ###.#
+  #########.#####.#
+DE..#######...###.#
+  #.#########.###.#
+FG..#########.....#
+  ###########.#####
+             Z
+             Z"
+            .lines()
+            .map(|line| format!("   {}", line))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        );
+
+        // Travelling through outer portal decreases layer level.
+        assert_eq!(
+            map.inner.get(&Pos(5, 8)),
+            Some(&TileType::Portal(Pos(12, 6), -1))
+        );
+
+        // Travelling through inner portal increases layer level.
+        assert_eq!(
+            map.inner.get(&Pos(12, 6)),
+            Some(&TileType::Portal(Pos(5, 8), 1))
+        );
+
+        assert_eq!(part_two(map, MAX_DEPTH), Some(26));
+    }
+
     #[test]
     fn test_parse_complex_map() {
         let map = Map::from(
@@ -424,7 +616,36 @@ FG..#########.....#
             .to_string(),
         );
 
-        assert_eq!(part_two(map), 26);
+        assert_eq!(part_two(map, MAX_DEPTH), Some(26));
+    }
+
+    #[test]
+    fn test_part_two_unreachable_exit() {
+        // The simple map, with the exit walled off.
+        let map = Map::from(
+            "         A
+         A
+  #######.#########
+  #######.........#
+  #######.#######.#
+  #######.#######.#
+  #######.#######.#
+  #####  B    ###.#
+BC...##  C    ###.#
+  ##.##       ###.#
+  ##...DE  F  ###.#
+  #####    G  ###.#
+  #########.#####.#
+DE..#######...###.#
+  #.#########.###.#
+FG..##########....#
+  ###########.#####
+             Z
+             Z"
+            .to_string(),
+        );
+
+        assert_eq!(part_two(map, 10), None);
     }
 
     #[test]
@@ -470,6 +691,6 @@ RE....#.#                           #......RF
                 .to_string(),
         );
 
-        assert_eq!(part_two(map), 396);
+        assert_eq!(part_two(map, MAX_DEPTH), Some(396));
     }
 }
@@ -1,23 +1,21 @@
 use std::io;
 
 extern crate intcode;
-use intcode::{Program, ProgramState};
+use intcode::Program;
 
 fn run_springdroid(program: Program, instructions: Vec<&str>) {
     let mut program = program;
 
     for instruction in instructions {
-        for character in instruction.chars() {
-            program.push_input(character as u8 as i64);
-        }
+        program.push_ascii(instruction);
     }
 
-    while let ProgramState::Output(output) = program.run() {
-        if output < 255 {
-            print!("{}", output as u8 as char);
-        } else {
-            println!("{}", output);
-        }
+    let (output, damage) = program.run_ascii().unwrap();
+
+    print!("{}", output);
+
+    if let Some(damage) = damage {
+        println!("{}", damage);
     }
 }
 
@@ -27,13 +25,13 @@ fn part_one(program: Program) {
     run_springdroid(
         program,
         vec![
-            "NOT A J\n", // J = !A (A = no ground)
-            "NOT B T\n", // T = !B (B = no ground)
-            "OR T J\n",  // J = !A || !B (A or B = no ground)
-            "NOT C T\n", // T = !C (C = no ground)
-            "OR T J\n",  // J = !A || !B || !C (A or B or C = no ground)
-            "AND D J\n", // J = (!A || !B || !C) && D (A or B or C = no ground, D = ground)
-            "WALK\n",
+            "NOT A J", // J = !A (A = no ground)
+            "NOT B T", // T = !B (B = no ground)
+            "OR T J",  // J = !A || !B (A or B = no ground)
+            "NOT C T", // T = !C (C = no ground)
+            "OR T J",  // J = !A || !B || !C (A or B or C = no ground)
+            "AND D J", // J = (!A || !B || !C) && D (A or B or C = no ground, D = ground)
+            "WALK",
         ],
     );
 }
@@ -57,19 +55,19 @@ fn part_two(program: Program) {
         // #####.#.#...#.###    #####.#.#...#.###    #####.#.#...#.###    #####.#.#...#.###
         //      ABCDEFGHI                ABCDEFGH                 ABCD
         vec![
-            "NOT A J\n", // J = !A (A = no ground)
-            "NOT B T\n", // T = !B (B = no ground)
-            "OR T J\n",  // J = !A || !B (A or B = no ground)
-            "NOT C T\n", // T = !C (C = no ground)
-            "OR T J\n",  // J = !A || !B || !C (A or B or C = no ground)
-            "AND D J\n", // J = (!A || !B || !C) && D (A or B or C = no ground, D = ground)
+            "NOT A J", // J = !A (A = no ground)
+            "NOT B T", // T = !B (B = no ground)
+            "OR T J",  // J = !A || !B (A or B = no ground)
+            "NOT C T", // T = !C (C = no ground)
+            "OR T J",  // J = !A || !B || !C (A or B or C = no ground)
+            "AND D J", // J = (!A || !B || !C) && D (A or B or C = no ground, D = ground)
             //
-            "NOT E T\n", // T = !E (E = no ground)
-            "NOT T T\n", // T = E (E = ground)
-            "OR H T\n",  // T = E || H (E or H = ground)
-            "AND T J\n", // J = (!A || !B || !C) && D && (E || H)
+            "NOT E T", // T = !E (E = no ground)
+            "NOT T T", // T = E (E = ground)
+            "OR H T",  // T = E || H (E or H = ground)
+            "AND T J", // J = (!A || !B || !C) && D && (E || H)
             //
-            "RUN\n",
+            "RUN",
         ],
     );
 }
@@ -32,9 +32,9 @@ impl Switch {
 
         let mut nat = NAT::new(self.programs.len());
 
-        // With my input, the Y values sent by the NAT to the first program area a series of
-        // decreasing numbers. Therefore, to find the first duplicate, its good enough to store the
-        // last number sent and compare. This may not be true for all inputs.
+        // The Y value of the last packet sent by the NAT to the first program. We're looking for
+        // the first Y value delivered twice in a row, so only the most recent needs to be stored;
+        // this holds regardless of the order in which the values arrive.
         let mut last_nat_send = None;
 
         loop {
@@ -44,7 +44,7 @@ impl Switch {
                 }
 
                 loop {
-                    match program.run() {
+                    match program.run().unwrap() {
                         ProgramState::Output(value) => {
                             let program_outputs = outputs.get_mut(index).unwrap();
 
@@ -79,7 +79,7 @@ impl Switch {
                         ProgramState::Wait => {
                             nat.waiting(index);
 
-                            if nat.is_stalled() {
+                            if nat.is_stalled(&inputs) {
                                 // Sent the NATs last packet to program 0.
                                 if let Some((x, y)) = nat.last_packet {
                                     let receiver = inputs.get_mut(0).unwrap();
@@ -87,10 +87,8 @@ impl Switch {
                                     receiver.push_back(x);
                                     receiver.push_back(y);
 
-                                    if let Some(previous) = last_nat_send {
-                                        if previous == y {
-                                            return y;
-                                        }
+                                    if last_nat_send == Some(y) {
+                                        return y;
                                     }
 
                                     last_nat_send = Some(y);
@@ -130,9 +128,11 @@ impl NAT {
         self.waiting[n] = true;
     }
 
-    /// Returns whether the network has stalled with all programs waiting for an input.
-    fn is_stalled(&self) -> bool {
+    /// Returns whether the network has stalled with all programs waiting for an input, and no
+    /// packets in the `inputs` queues still waiting to be delivered.
+    fn is_stalled(&self, inputs: &[VecDeque<i64>]) -> bool {
         self.waiting.iter().filter(|&&wait| wait).count() == self.waiting.len()
+            && inputs.iter().all(|queue| queue.is_empty())
     }
 
     fn ready(&mut self, n: usize) {
@@ -146,8 +146,7 @@ fn initialize_switch(intcodes: Vec<i64>) -> Switch {
     for id in 0..50 {
         let mut program = Program::new(intcodes.clone());
 
-        program.push_input(id);
-        program.push_input(-1);
+        program.push_inputs(&[id, -1]);
 
         switch.push(program);
     }
@@ -171,3 +170,69 @@ fn main() -> Result<(), io::Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a program which sends a packet with each of the `ys` to the NAT in turn, reading the
+    /// packet delivered back by the NAT before sending the next.
+    fn nat_echo_program(ys: &[i64]) -> Program {
+        let mut intcodes = Vec::new();
+
+        for &y in ys {
+            intcodes.extend_from_slice(&[104, 255, 104, 0, 104, y, 3, 1000, 3, 1000]);
+        }
+
+        intcodes.push(99);
+
+        Program::new(intcodes)
+    }
+
+    #[test]
+    fn test_nat_is_stalled() {
+        let mut nat = NAT::new(2);
+        let mut inputs = vec![VecDeque::new(); 2];
+
+        nat.waiting(0);
+        assert!(!nat.is_stalled(&inputs));
+
+        nat.waiting(1);
+        assert!(nat.is_stalled(&inputs));
+
+        // A packet has been queued for the second program, which has yet to read it.
+        inputs[1].push_back(1);
+        inputs[1].push_back(2);
+
+        assert!(!nat.is_stalled(&inputs));
+
+        inputs[1].clear();
+        assert!(nat.is_stalled(&inputs));
+    }
+
+    #[test]
+    fn test_part_two_non_monotonic() {
+        let mut switch = Switch::new();
+
+        // 5 is delivered twice, but not in a row. 7 is the first to be delivered twice in a row.
+        switch.push(nat_echo_program(&[5, 3, 5, 8, 7, 7, 3, 3]));
+
+        assert_eq!(switch.run(false), 7);
+    }
+
+    #[test]
+    fn test_part_one() -> Result<(), io::Error> {
+        let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
+        assert_eq!(part_one(intcodes), 17286);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_part_two() -> Result<(), io::Error> {
+        let intcodes = intcode::load_intcodes_from_file("data/intcodes.txt")?;
+        assert_eq!(part_two(intcodes), 11249);
+
+        Ok(())
+    }
+}
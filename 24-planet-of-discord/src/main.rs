@@ -1,5 +1,5 @@
 use std::collections::{HashMap, HashSet};
-use std::{fs, io};
+use std::{fmt, fs, io};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum TileType {
@@ -234,6 +234,30 @@ impl From<String> for Map {
     }
 }
 
+impl fmt::Display for Map {
+    /// Renders the map with one row per line, using `#` for infested tiles, `.` for empty tiles, and
+    /// `?` for the tile containing the recursive map.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for x in 0..self.width() {
+                let character = match self.inner.get(&Pos(x as i32, y as i32)) {
+                    Some(TileType::Infested) => '#',
+                    Some(TileType::RecursiveMap) => '?',
+                    _ => '.',
+                };
+
+                write!(f, "{}", character)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 struct MultiMap(Vec<Map>);
 
 impl MultiMap {
@@ -271,6 +295,21 @@ impl MultiMap {
     }
 }
 
+impl fmt::Display for MultiMap {
+    /// Renders each layer in turn, prefixed by its depth, with a blank line between layers.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, map) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, "\n\n")?;
+            }
+
+            write!(f, "Depth {}:\n{}", map.layer, map)?;
+        }
+
+        Ok(())
+    }
+}
+
 fn part_one(map: Map) -> i32 {
     let mut map = map;
     let mut seen = HashSet::new();
@@ -289,13 +328,19 @@ fn part_one(map: Map) -> i32 {
     }
 }
 
-fn part_two(map: Map) -> usize {
+/// Simulates the bugs on recursive maps for the given number of `minutes`, returning how many bugs
+/// are present at the end.
+fn part_two(map: Map, minutes: usize) -> usize {
+    // Bugs can spread at most one layer up and down every two minutes. Add a couple of spare
+    // layers each side so that they never reach the outermost maps, which have no layer beyond.
+    let layers = minutes + 3;
+
     let mut map = map;
-    map.layer = 100;
+    map.layer = layers / 2;
 
-    let mut multi = MultiMap::new(map, 250);
+    let mut multi = MultiMap::new(map, layers);
 
-    for _ in 0..200 {
+    for _ in 0..minutes {
         multi = multi.step_forward();
     }
 
@@ -317,7 +362,7 @@ fn main() -> Result<(), io::Error> {
     println!("Part one: {}", part_one(map));
 
     let map = Map::from(fs::read_to_string("data/map.txt")?);
-    println!("Part two: {}", part_two(map));
+    println!("Part two: {}", part_two(map, 200));
 
     Ok(())
 }
@@ -351,6 +396,51 @@ mod tests {
         assert_eq!(map.inner.get(&Pos(5, 0)), None);
     }
 
+    #[test]
+    fn test_display_map() {
+        let input = trim_leading_whitespace(
+            "....#
+             #..#.
+             #.?##
+             ..#..
+             #....",
+        );
+
+        assert_eq!(Map::from(input.clone()).to_string(), input);
+    }
+
+    #[test]
+    fn test_display_multimap() {
+        let map = Map::from(trim_leading_whitespace(
+            "....#
+             #..#.
+             #..##
+             ..#..
+             #....",
+        ));
+
+        let multi = MultiMap::new(map, 2);
+
+        assert_eq!(
+            multi.to_string(),
+            trim_leading_whitespace(
+                "Depth 0:
+                 ....#
+                 #..#.
+                 #.?##
+                 ..#..
+                 #....
+
+                 Depth 1:
+                 .....
+                 .....
+                 .....
+                 .....
+                 ....."
+            )
+        );
+    }
+
     #[test]
     fn test_infested_neighbors() {
         let map = Map::from(trim_leading_whitespace(
@@ -465,6 +555,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_part_two() {
+        let map = Map::from(trim_leading_whitespace(
+            "....#
+             #..#.
+             #..##
+             ..#..
+             #....",
+        ));
+
+        assert_eq!(part_two(map, 10), 99);
+    }
+
     #[test]
     fn test_neighbors_above() {
         let map = Map::from(trim_leading_whitespace(
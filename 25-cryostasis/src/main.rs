@@ -12,33 +12,624 @@
 /// 3. Move  south. Take the tambourine.
 /// 4. Move north, west, south, south, west, and south to the kitcen. Take the easter egg.
 /// 5. Move west to the security checkpoint.
-use std::io::{self, BufRead};
+/// 6. Move west onto the pressure-sensitive floor.
+///
+/// These steps may be replayed with `cargo run -- data/solution.txt`. Alternatively, running
+/// `cargo run -- data/checkpoint.txt --solve` collects all eight items which are safe to pick up,
+/// and tries every combination of them at the security checkpoint until one is accepted.
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
 
 extern crate intcode;
 use intcode::{Program, ProgramState};
 
+/// Runs the program, writing its ASCII output to `output`. Each time the program waits for input,
+/// the next of the `commands` is sent to it. Returns once the program halts, or when it waits for
+/// input and there are no commands left.
+fn run<I, W>(program: &mut Program, commands: I, output: &mut W) -> Result<(), io::Error>
+where
+    I: IntoIterator<Item = String>,
+    W: Write,
+{
+    let mut commands = commands.into_iter();
+
+    loop {
+        match program.run().unwrap() {
+            ProgramState::Output(value) => {
+                write!(output, "{}", value as u8 as char)?;
+            }
+            ProgramState::Wait => match commands.next() {
+                Some(command) => program.push_ascii(&command),
+                None => break,
+            },
+            ProgramState::Halt => break,
+        }
+    }
+
+    output.flush()
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    /// Parses a direction from a command, or from the list of doors in a room description.
+    fn parse(string: &str) -> Option<Direction> {
+        match string {
+            "north" => Some(Direction::North),
+            "south" => Some(Direction::South),
+            "east" => Some(Direction::East),
+            "west" => Some(Direction::West),
+            _ => None,
+        }
+    }
+
+    /// The command to be sent to the droid to move in this direction.
+    fn as_command(&self) -> &'static str {
+        match self {
+            Direction::North => "north",
+            Direction::South => "south",
+            Direction::East => "east",
+            Direction::West => "west",
+        }
+    }
+
+    /// Returns the direction which undoes a movement in this direction.
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
+/// A room on the ship, as described by the droid upon entering it.
+#[derive(Debug, PartialEq)]
+struct Room {
+    name: String,
+    doors: Vec<Direction>,
+    items: Vec<String>,
+}
+
+/// Parses each of the room descriptions in the droid output, in the order in which they appear.
+/// There will usually be one; more than one means the droid was moved again after entering the
+/// first, such as when it is ejected from the pressure-sensitive floor.
+fn parse_rooms(output: &str) -> Vec<Room> {
+    let mut rooms = Vec::new();
+
+    // Which list in the description the following lines belong to, if any.
+    let mut in_doors = false;
+    let mut in_items = false;
+
+    for line in output.lines() {
+        if line.starts_with("== ") && line.ends_with(" ==") {
+            rooms.push(Room {
+                name: line.trim_matches(|c| c == '=' || c == ' ').to_string(),
+                doors: Vec::new(),
+                items: Vec::new(),
+            });
+        } else if line == "Doors here lead:" {
+            in_doors = true;
+        } else if line == "Items here:" {
+            in_items = true;
+        } else if let (Some(room), Some(entry)) = (rooms.last_mut(), line.strip_prefix("- ")) {
+            if in_doors {
+                room.doors.extend(Direction::parse(entry));
+            } else if in_items {
+                room.items.push(entry.to_string());
+            }
+        } else {
+            in_doors = false;
+            in_items = false;
+        }
+    }
+
+    rooms
+}
+
+/// Builds a graph of the rooms on the ship as the droid explores them.
+struct ShipMap {
+    rooms: HashMap<String, Room>,
+    /// The room reached by leaving a room in a direction, keyed by the name of the room which was
+    /// left and the direction.
+    connections: HashMap<(String, Direction), String>,
+    /// The name of the room the droid is in.
+    current: Option<String>,
+}
+
+impl ShipMap {
+    fn new() -> ShipMap {
+        ShipMap {
+            rooms: HashMap::new(),
+            connections: HashMap::new(),
+            current: None,
+        }
+    }
+
+    /// Records the `output` from the droid after it was sent the `command`. When the command moved
+    /// the droid into a room, that room is connected with the one it came from.
+    fn explore(&mut self, command: &str, output: &str) {
+        let rooms = parse_rooms(output);
+
+        if let (Some(current), Some(direction), Some(entered)) =
+            (&self.current, Direction::parse(command), rooms.first())
+        {
+            self.connections
+                .insert((current.clone(), direction), entered.name.clone());
+
+            self.connections.insert(
+                (entered.name.clone(), direction.opposite()),
+                current.clone(),
+            );
+        }
+
+        if let Some(last) = rooms.last() {
+            self.current = Some(last.name.clone());
+        }
+
+        for room in rooms {
+            self.rooms.insert(room.name.clone(), room);
+        }
+    }
+
+    /// Returns the doors in the current room which the droid has not yet been through.
+    fn unexplored(&self) -> Vec<Direction> {
+        let current = match &self.current {
+            Some(current) => current,
+            None => return Vec::new(),
+        };
+
+        self.rooms[current]
+            .doors
+            .iter()
+            .filter(|&&door| !self.connections.contains_key(&(current.clone(), door)))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Something which can be sent a text command, and replies with text output.
+trait Droid {
+    /// Sends the `command`, returning all the output until the droid next waits for a command.
+    fn send(&mut self, command: &str) -> String;
+}
+
+impl Droid for Program {
+    fn send(&mut self, command: &str) -> String {
+        let mut output = String::new();
+
+        self.push_ascii(command);
+
+        // Collect output until the droid waits for the next command, or halts.
+        while let ProgramState::Output(value) = self.run().unwrap() {
+            output.push(value as u8 as char);
+        }
+
+        output
+    }
+}
+
+/// The outcome of stepping onto the pressure-sensitive floor.
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    /// Droids on the ship are heavier; more, or heavier, items must be held.
+    TooLight,
+    /// Droids on the ship are lighter; fewer, or lighter, items must be held.
+    TooHeavy,
+    /// The droid was allowed through.
+    Accepted,
+    /// The output wasn't recognised; the droid probably didn't step onto the floor at all.
+    Unknown,
+}
+
+impl Verdict {
+    /// Determines the verdict from the output of the droid after stepping onto the floor.
+    fn from_output(output: &str) -> Verdict {
+        if output.contains("heavier than the detected value") {
+            Verdict::TooLight
+        } else if output.contains("lighter than the detected value") {
+            Verdict::TooHeavy
+        } else if output.contains("Analysis complete") || output.contains("main airlock") {
+            Verdict::Accepted
+        } else {
+            Verdict::Unknown
+        }
+    }
+}
+
+/// Describes why the droid was unable to get past the security checkpoint.
+#[derive(Debug, PartialEq)]
+enum CheckpointError {
+    /// Every combination of the held items was rejected by the floor.
+    Rejected,
+    /// Moving towards the floor produced output which wasn't a verdict.
+    UnexpectedOutput(String),
+}
+
+/// Returns the names of the items currently held by the droid.
+fn inventory<D: Droid>(droid: &mut D) -> Vec<String> {
+    droid
+        .send("inv")
+        .lines()
+        .filter_map(|line| line.strip_prefix("- "))
+        .map(|item| item.to_string())
+        .collect()
+}
+
+/// Finds which of the items held by the droid will get it past the pressure-sensitive floor, which
+/// lies in the given `direction` from the security checkpoint at which the droid is standing.
+///
+/// Every combination of the held items is tried in turn, dropping and taking items as needed,
+/// until the floor accepts one. Returns the accepted items, and the output from stepping onto the
+/// floor.
+///
+/// Returns an error if no combination was accepted, or as soon as the output from moving in the
+/// `direction` isn't recognised as a verdict from the floor.
+fn solve_checkpoint<D: Droid>(
+    droid: &mut D,
+    direction: &str,
+) -> Result<(Vec<String>, String), CheckpointError> {
+    let items = inventory(droid);
+    let mut held = vec![true; items.len()];
+
+    for combination in 0..(1 << items.len()) {
+        for (index, item) in items.iter().enumerate() {
+            let wanted = combination & (1 << index) != 0;
+
+            if wanted && !held[index] {
+                droid.send(&format!("take {}", item));
+            } else if !wanted && held[index] {
+                droid.send(&format!("drop {}", item));
+            }
+
+            held[index] = wanted;
+        }
+
+        let output = droid.send(direction);
+
+        match Verdict::from_output(&output) {
+            Verdict::Accepted => {
+                let accepted = items
+                    .into_iter()
+                    .zip(held)
+                    .filter(|(_, held)| *held)
+                    .map(|(item, _)| item)
+                    .collect();
+
+                return Ok((accepted, output));
+            }
+            Verdict::Unknown => return Err(CheckpointError::UnexpectedOutput(output)),
+            Verdict::TooLight | Verdict::TooHeavy => {}
+        }
+    }
+
+    Err(CheckpointError::Rejected)
+}
+
+/// Reads the newline-separated commands in the file at `path`, skipping blank lines.
+fn read_commands(path: &str) -> Result<Vec<String>, io::Error> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
+
 fn main() -> Result<(), io::Error> {
     let mut program = Program::from_file("data/intcodes.txt")?;
-    let stdin = io::stdin();
 
-    loop {
-        match program.run() {
-            ProgramState::Output(output) => {
-                print!("{}", output as u8 as char);
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // A file of commands may be given as an argument. They are sent to the droid before handing
+    // over to the commands typed into stdin.
+    let script = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(path) => read_commands(path)?,
+        None => Vec::new(),
+    };
+
+    // With the --solve flag the script should leave the droid at the security checkpoint, holding
+    // the items to be tried.
+    if args.iter().any(|arg| arg == "--solve") {
+        let mut map = ShipMap::new();
+        let mut intro = Vec::new();
+
+        run(&mut program, Vec::new(), &mut intro)?;
+        map.explore("", &String::from_utf8_lossy(&intro));
+
+        for command in script {
+            let output = program.send(&command);
+
+            print!("{}", output);
+            map.explore(&command, &output);
+        }
+
+        // The only door from the checkpoint which hasn't been explored leads to the floor.
+        let direction = *map
+            .unexplored()
+            .first()
+            .expect("Expected an unexplored door from the security checkpoint");
+
+        match solve_checkpoint(&mut program, direction.as_command()) {
+            Ok((items, output)) => {
+                println!("Accepted items: {}", items.join(", "));
+                print!("{}", output);
+            }
+            Err(CheckpointError::Rejected) => println!("No combination of items was accepted"),
+            Err(CheckpointError::UnexpectedOutput(output)) => {
+                println!("Expected a verdict from the floor, got:\n{}", output)
             }
-            ProgramState::Wait => {
-                let mut iter = stdin.lock().lines();
-                let input = iter.next().unwrap().unwrap();
+        }
 
-                for character in input.bytes() {
-                    program.push_input(character as i64);
-                }
+        return Ok(());
+    }
+
+    let stdin = io::stdin();
+    let typed = stdin.lock().lines().map(|line| line.unwrap());
 
-                program.push_input('\n' as i64);
+    run(
+        &mut program,
+        script.into_iter().chain(typed),
+        &mut io::stdout(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_commands() -> Result<(), io::Error> {
+        // Reads each input value and outputs it again, forever.
+        let mut program = Program::new(vec![3, 100, 4, 100, 1105, 1, 0]);
+        let mut output = Vec::new();
+
+        let commands = vec!["north".to_string(), "take mutex".to_string()];
+
+        run(&mut program, commands, &mut output)?;
+
+        assert_eq!(String::from_utf8(output).unwrap(), "north\ntake mutex\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_solution() -> Result<(), io::Error> {
+        let mut program = Program::from_file("data/intcodes.txt")?;
+        let mut output = Vec::new();
+
+        let commands = read_commands("data/solution.txt")?;
+
+        run(&mut program, commands, &mut output)?;
+
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("typing 295944 on the keypad"));
+
+        Ok(())
+    }
+
+    /// Pretends to be a droid at the security checkpoint, which will only be accepted by the
+    /// pressure-sensitive floor when holding exactly the `accepted` items.
+    struct MockDroid {
+        held: Vec<String>,
+        accepted: Vec<String>,
+        attempts: usize,
+    }
+
+    impl Droid for MockDroid {
+        fn send(&mut self, command: &str) -> String {
+            if command == "inv" {
+                let items: Vec<String> = self
+                    .held
+                    .iter()
+                    .map(|item| format!("- {}\n", item))
+                    .collect();
+                format!("\nItems in your inventory:\n{}\nCommand?\n", items.concat())
+            } else if let Some(item) = command.strip_prefix("take ") {
+                self.held.push(item.to_string());
+                format!("\nYou take the {}.\n\nCommand?\n", item)
+            } else if let Some(item) = command.strip_prefix("drop ") {
+                self.held.retain(|held| held != item);
+                format!("\nYou drop the {}.\n\nCommand?\n", item)
+            } else if command != "west" {
+                "\nYou can't go that way.\n\nCommand?\n".to_string()
+            } else {
+                self.attempts += 1;
+
+                let mut held = self.held.clone();
+                held.sort();
+
+                if held == self.accepted {
+                    "\n== Pressure-Sensitive Floor ==\nAnalysis complete!\n".to_string()
+                } else if held.len() < self.accepted.len() {
+                    "Droids on this ship are heavier than the detected value!\n".to_string()
+                } else {
+                    "Droids on this ship are lighter than the detected value!\n".to_string()
+                }
             }
-            ProgramState::Halt => break,
         }
     }
 
-    Ok(())
+    #[test]
+    fn test_solve_checkpoint() {
+        let held: Vec<String> = vec!["mutex", "tambourine", "easter egg", "fuel cell"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        let mut droid = MockDroid {
+            held: held.clone(),
+            accepted: vec!["easter egg".to_string(), "mutex".to_string()],
+            attempts: 0,
+        };
+
+        let (items, output) = solve_checkpoint(&mut droid, "west").unwrap();
+
+        assert_eq!(items, vec!["mutex", "easter egg"]);
+        assert!(output.contains("Analysis complete!"));
+        assert!(droid.attempts > 1);
+
+        // No combination will be accepted when an item is missing.
+        let mut droid = MockDroid {
+            held,
+            accepted: vec!["astronaut ice cream".to_string()],
+            attempts: 0,
+        };
+
+        assert_eq!(
+            solve_checkpoint(&mut droid, "west"),
+            Err(CheckpointError::Rejected)
+        );
+        assert_eq!(droid.attempts, 16);
+
+        // The floor isn't to the north, so there's no verdict.
+        let mut droid = MockDroid {
+            held: vec!["mutex".to_string()],
+            accepted: vec!["mutex".to_string()],
+            attempts: 0,
+        };
+
+        assert_eq!(
+            solve_checkpoint(&mut droid, "north"),
+            Err(CheckpointError::UnexpectedOutput(
+                "\nYou can't go that way.\n\nCommand?\n".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_verdict() {
+        assert_eq!(
+            Verdict::from_output("Droids on this ship are heavier than the detected value!"),
+            Verdict::TooLight
+        );
+
+        assert_eq!(
+            Verdict::from_output("Droids on this ship are lighter than the detected value!"),
+            Verdict::TooHeavy
+        );
+
+        assert_eq!(
+            Verdict::from_output("Analysis complete! You may proceed."),
+            Verdict::Accepted
+        );
+
+        assert_eq!(
+            Verdict::from_output("You can't go that way."),
+            Verdict::Unknown
+        );
+
+        assert_eq!(Verdict::from_output(""), Verdict::Unknown);
+    }
+
+    #[test]
+    fn test_solve_real_checkpoint() -> Result<(), io::Error> {
+        let mut program = Program::from_file("data/intcodes.txt")?;
+
+        run(
+            &mut program,
+            read_commands("data/checkpoint.txt")?,
+            &mut io::sink(),
+        )?;
+
+        assert_eq!(inventory(&mut program).len(), 8);
+
+        let (mut items, output) = solve_checkpoint(&mut program, "west").unwrap();
+        items.sort();
+
+        assert_eq!(
+            items,
+            vec!["astronaut ice cream", "easter egg", "mutex", "tambourine"]
+        );
+
+        assert!(output.contains("typing 295944 on the keypad"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rooms() {
+        let output = concat!(
+            "\n\n\n== Kitchen ==\n",
+            "Everything's freeze-dried.\n",
+            "\n",
+            "Doors here lead:\n",
+            "- north\n",
+            "- west\n",
+            "\n",
+            "Items here:\n",
+            "- easter egg\n",
+            "\n",
+            "Command?\n",
+        );
+
+        assert_eq!(
+            parse_rooms(output),
+            vec![Room {
+                name: "Kitchen".to_string(),
+                doors: vec![Direction::North, Direction::West],
+                items: vec!["easter egg".to_string()],
+            }]
+        );
+
+        assert_eq!(
+            parse_rooms("\nYou take the easter egg.\n\nCommand?\n"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_ship_map() -> Result<(), io::Error> {
+        let mut program = Program::from_file("data/intcodes.txt")?;
+        let mut map = ShipMap::new();
+        let mut intro = Vec::new();
+
+        run(&mut program, Vec::new(), &mut intro)?;
+        map.explore("", &String::from_utf8_lossy(&intro));
+
+        for command in read_commands("data/checkpoint.txt")? {
+            let output = program.send(&command);
+            map.explore(&command, &output);
+        }
+
+        assert_eq!(map.current, Some("Security Checkpoint".to_string()));
+        assert_eq!(map.unexplored(), vec![Direction::West]);
+
+        assert_eq!(
+            map.connections
+                .get(&("Security Checkpoint".to_string(), Direction::East)),
+            Some(&"Kitchen".to_string())
+        );
+
+        assert_eq!(
+            map.connections
+                .get(&("Kitchen".to_string(), Direction::West)),
+            Some(&"Security Checkpoint".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_commands() -> Result<(), io::Error> {
+        let path = env::temp_dir().join("day-twenty-five-commands.txt");
+        fs::write(&path, "south\n\n  take mutex\neast\n")?;
+
+        assert_eq!(
+            read_commands(path.to_str().unwrap())?,
+            vec!["south", "take mutex", "east"]
+        );
+
+        fs::remove_file(path)
+    }
 }
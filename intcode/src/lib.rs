@@ -9,23 +9,22 @@
 //! The program pauses execution whenever an output is produced; [`Program::run()`] will return
 //! a [`ProgramState::Output`] containing an i64 allowing you to do what you need with the output,
 //! and then resume execution of the program by calling [`Program::run()`] again. When the program
-//! finishes executing, [`ProgramState::Halt`] is returned. The `Halt` contains an `Option`: this
-//! will be `Some` if the yielded a value and them immediately finished.
+//! finishes executing, [`ProgramState::Halt`] is returned. If the program is malformed -- for
+//! example it contains an unknown opcode -- `run` returns an [`IntcodeError`].
 //!
 //! A typical pattern where you need to act on the outputs of the program during execution is to
 //! use a loop:
 //!
 //! ```norun
 //! loop {
-//!     match self.program.run() {
+//!     match self.program.run()? {
 //!         ProgramState::Output(value) => {
 //!             // do something with output
 //!         },
-//!         ProgramState::Halt(Some(value)) => {
-//!             // optionally do something with output
-//!             break;
+//!         ProgramState::Wait => {
+//!             // push more input
 //!         },
-//!         ProgramState::Halt(None) => break,
+//!         ProgramState::Halt => break,
 //!     }
 //! }
 //! ```
@@ -34,23 +33,147 @@
 //! instead use [`Program::run_capturing_output()`] which will return a `Vec<i64>` containing all
 //! of the outputs produced by the program during execution.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
+use std::num::ParseIntError;
+use std::str::FromStr;
 
 /// Provided a path to a file on disk, loads the intcodes contained within and returns a vector.
+///
+/// Returns an error if the file cannot be read, or an error of kind [`io::ErrorKind::InvalidData`]
+/// if any of the intcodes are not valid integers.
 pub fn load_intcodes_from_file(path: &str) -> Result<Vec<i64>, io::Error> {
     let file = File::open(path)?;
     let mut reader = BufReader::new(file);
 
     let mut first_line = String::new();
-    reader.read_line(&mut first_line).unwrap();
+    reader.read_line(&mut first_line)?;
+
+    parse_intcodes(&first_line).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
 
-    Ok(first_line
+/// Parses a comma-separated list of intcodes, such as `"1002,4,3,4,33"`. Surrounding whitespace
+/// is ignored.
+pub fn parse_intcodes(intcodes: &str) -> Result<Vec<i64>, ParseIntcodesError> {
+    intcodes
         .trim()
-        .split(",")
-        .map(|intcode| intcode.parse::<i64>().unwrap())
-        .collect())
+        .split(',')
+        .map(|intcode| {
+            intcode
+                .trim()
+                .parse::<i64>()
+                .map_err(|source| ParseIntcodesError {
+                    intcode: intcode.to_string(),
+                    source,
+                })
+        })
+        .collect()
+}
+
+/// Describes an intcode which could not be parsed as an integer.
+#[derive(Debug, PartialEq)]
+pub struct ParseIntcodesError {
+    intcode: String,
+    source: ParseIntError,
+}
+
+impl fmt::Display for ParseIntcodesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid intcode {:?}: {}", self.intcode, self.source)
+    }
+}
+
+impl error::Error for ParseIntcodesError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Describes why a program was unable to continue running.
+#[derive(Debug, PartialEq)]
+pub enum IntcodeError {
+    /// The program encountered an instruction with an opcode it doesn't know about.
+    UnknownOpcode(i64),
+    /// An instruction specified a parameter mode other than position, immediate, or relative.
+    InvalidParamMode(i64),
+    /// The program requires an input value, but none are available and the caller is unable to
+    /// wait for more.
+    NoInput,
+    /// An instruction attempted to access a memory address below zero.
+    NegativeAddress(i64),
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::UnknownOpcode(opcode) => write!(f, "Unknown opcode: {}", opcode),
+            IntcodeError::InvalidParamMode(mode) => write!(f, "Invalid param mode: {}", mode),
+            IntcodeError::NoInput => write!(f, "No input available"),
+            IntcodeError::NegativeAddress(address) => {
+                write!(f, "Negative memory address: {}", address)
+            }
+        }
+    }
+}
+
+impl error::Error for IntcodeError {}
+
+/// Converts the `opcodes` of a program into a human-readable list of instructions, one per line.
+///
+/// Each instruction is shown as a mnemonic followed by its parameters, with the address to which it
+/// writes shown last after an arrow. For example, `1002,4,3,4` becomes `MUL [4] #3 -> [4]`. Values
+/// which aren't valid instructions -- often data stored at the end of the program -- are shown as
+/// `DATA <value>`.
+pub fn disassemble(opcodes: &[i64]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pointer = 0;
+
+    while pointer < opcodes.len() {
+        let instruction = match InstructionWithMode::from_intcode(opcodes[pointer]) {
+            Ok(instruction) if pointer + instruction.size() <= opcodes.len() => instruction,
+            _ => {
+                lines.push(format!("DATA {}", opcodes[pointer]));
+                pointer += 1;
+                continue;
+            }
+        };
+
+        let modes = [
+            &instruction.mode_one,
+            &instruction.mode_two,
+            &instruction.mode_three,
+        ];
+
+        let params: Vec<String> = modes
+            .iter()
+            .zip(&opcodes[pointer + 1..pointer + instruction.size()])
+            .map(|(mode, param)| mode.format(*param))
+            .collect();
+
+        let mnemonic = instruction.instruction.mnemonic();
+
+        lines.push(match instruction.instruction {
+            Instruction::Add | Instruction::Mul | Instruction::LessThan | Instruction::Equal => {
+                format!("{} {} {} -> {}", mnemonic, params[0], params[1], params[2])
+            }
+            Instruction::JumpIfTrue | Instruction::JumpIfFalse => {
+                format!("{} {} {}", mnemonic, params[0], params[1])
+            }
+            Instruction::Input => format!("{} -> {}", mnemonic, params[0]),
+            Instruction::Output | Instruction::SetRelativeBase => {
+                format!("{} {}", mnemonic, params[0])
+            }
+            Instruction::Exit => mnemonic.to_string(),
+        });
+
+        pointer += instruction.size();
+    }
+
+    lines
 }
 
 /// Parameters may be retrieved from the program in one of two ways.
@@ -69,25 +192,44 @@ enum ParamMode {
 }
 
 impl ParamMode {
-    fn from_digit(digit: i64) -> ParamMode {
+    fn from_digit(digit: i64) -> Result<ParamMode, IntcodeError> {
         match digit {
-            0 => ParamMode::Position,
-            1 => ParamMode::Immediate,
-            2 => ParamMode::Relative,
-            _ => panic!("Invalid param mode: {}", digit),
+            0 => Ok(ParamMode::Position),
+            1 => Ok(ParamMode::Immediate),
+            2 => Ok(ParamMode::Relative),
+            _ => Err(IntcodeError::InvalidParamMode(digit)),
+        }
+    }
+
+    /// Formats a parameter for disassembly: `[n]` for position mode, `#n` for immediate mode, and
+    /// `R+n` or `R-n` for relative mode.
+    fn format(&self, param: i64) -> String {
+        match self {
+            ParamMode::Position => format!("[{}]", param),
+            ParamMode::Immediate => format!("#{}", param),
+            ParamMode::Relative if param < 0 => format!("R{}", param),
+            ParamMode::Relative => format!("R+{}", param),
         }
     }
 
-    fn value_at(&self, position: usize, program: &Program) -> i64 {
-        program.read(self.position(position, &program))
+    fn value_at(&self, position: usize, program: &Program) -> Result<i64, IntcodeError> {
+        Ok(program.read(self.position(position, &program)?))
     }
 
-    fn position(&self, position: usize, program: &Program) -> usize {
-        match self {
-            ParamMode::Position => program.read(position) as usize,
-            ParamMode::Immediate => position,
-            ParamMode::Relative => (program.relative_base as i64 + program.read(position)) as usize,
+    /// Returns the memory address referred to by the parameter at `position`, or an error if the
+    /// parameter refers to an address below zero.
+    fn position(&self, position: usize, program: &Program) -> Result<usize, IntcodeError> {
+        let address = match self {
+            ParamMode::Position => program.read(position),
+            ParamMode::Immediate => return Ok(position),
+            ParamMode::Relative => program.relative_base + program.read(position),
+        };
+
+        if address < 0 {
+            return Err(IntcodeError::NegativeAddress(address));
         }
+
+        Ok(address as usize)
     }
 }
 
@@ -106,19 +248,19 @@ enum Instruction {
 }
 
 impl Instruction {
-    fn from_opcode(digit: i64) -> Instruction {
+    fn from_opcode(digit: i64) -> Result<Instruction, IntcodeError> {
         match digit {
-            1 => Instruction::Add,
-            2 => Instruction::Mul,
-            3 => Instruction::Input,
-            4 => Instruction::Output,
-            5 => Instruction::JumpIfTrue,
-            6 => Instruction::JumpIfFalse,
-            7 => Instruction::LessThan,
-            8 => Instruction::Equal,
-            9 => Instruction::SetRelativeBase,
-            99 => Instruction::Exit,
-            _ => panic!("Unknown opcode: {}", digit),
+            1 => Ok(Instruction::Add),
+            2 => Ok(Instruction::Mul),
+            3 => Ok(Instruction::Input),
+            4 => Ok(Instruction::Output),
+            5 => Ok(Instruction::JumpIfTrue),
+            6 => Ok(Instruction::JumpIfFalse),
+            7 => Ok(Instruction::LessThan),
+            8 => Ok(Instruction::Equal),
+            9 => Ok(Instruction::SetRelativeBase),
+            99 => Ok(Instruction::Exit),
+            _ => Err(IntcodeError::UnknownOpcode(digit)),
         }
     }
 
@@ -141,6 +283,22 @@ impl Instruction {
         }
     }
 
+    /// A short name for the instruction used when disassembling a program.
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Add => "ADD",
+            Instruction::Mul => "MUL",
+            Instruction::Input => "IN",
+            Instruction::Output => "OUT",
+            Instruction::JumpIfTrue => "JT",
+            Instruction::JumpIfFalse => "JF",
+            Instruction::LessThan => "LT",
+            Instruction::Equal => "EQ",
+            Instruction::SetRelativeBase => "ARB",
+            Instruction::Exit => "HALT",
+        }
+    }
+
     /// Returns if the instruction will adjust the program pointer.
     fn jumps(&self) -> bool {
         match self {
@@ -162,13 +320,13 @@ struct InstructionWithMode {
 impl InstructionWithMode {
     /// Converts an i64 to a InstructionWithMode describing the instruction and up to three parameter
     /// modes.
-    fn from_intcode(intcode: i64) -> InstructionWithMode {
-        InstructionWithMode {
-            instruction: Instruction::from_opcode(intcode % 100),
-            mode_one: ParamMode::from_digit((intcode / 100) % 10),
-            mode_two: ParamMode::from_digit((intcode / 1000) % 10),
-            mode_three: ParamMode::from_digit(intcode / 10000),
-        }
+    fn from_intcode(intcode: i64) -> Result<InstructionWithMode, IntcodeError> {
+        Ok(InstructionWithMode {
+            instruction: Instruction::from_opcode(intcode % 100)?,
+            mode_one: ParamMode::from_digit((intcode / 100) % 10)?,
+            mode_two: ParamMode::from_digit((intcode / 1000) % 10)?,
+            mode_three: ParamMode::from_digit(intcode / 10000)?,
+        })
     }
 
     /// See [Instruction::size]
@@ -202,31 +360,190 @@ pub enum ProgramState {
     Wait,
 }
 
+/// Returned by [`Program::step()`] to describe the outcome of executing a single instruction.
+#[derive(Debug, PartialEq)]
+pub enum StepResult {
+    /// The instruction was executed and produced no output.
+    Continued,
+    /// The instruction output a value.
+    Output(i64),
+    /// The instruction requires an input value, but none are available. The instruction was not
+    /// executed.
+    NeedsInput,
+    /// The program has terminated and will not -- or cannot -- continue.
+    Halted,
+}
+
+/// A copy of the state of a running program, created with [`Program::snapshot()`] and returned to
+/// with [`Program::restore()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgramSnapshot {
+    opcodes: Vec<i64>,
+    overflow: HashMap<usize, i64>,
+    pointer: usize,
+    relative_base: i64,
+    inputs: VecDeque<i64>,
+    instructions_executed: u64,
+    halted: bool,
+}
+
+/// A function which supplies inputs to a program.
+type InputSource = Box<dyn FnMut() -> Option<i64> + Send>;
+
 /// The opcode program!
 pub struct Program {
     opcodes: Vec<i64>,
+    // Memory beyond the end of `opcodes`. Programs may write to very high addresses while leaving
+    // most of those below untouched, so these are stored sparsely.
+    overflow: HashMap<usize, i64>,
+    // One past the highest address in memory: the end of `opcodes`, or of the highest address
+    // written in `overflow`. Unwritten addresses below this read as 0, like any other memory.
+    memory_len: usize,
+    // The opcodes with which the program was created, restored by `reset`.
+    initial_opcodes: Vec<i64>,
     pointer: usize,
     inputs: VecDeque<i64>,
-    relative_base: usize,
+    // Provides inputs when the `inputs` queue is empty. Not copied to clones of the program.
+    input_source: Option<InputSource>,
+    relative_base: i64,
+    instructions_executed: u64,
+    // Whether the program has executed an Exit instruction, so that it is counted only once.
+    halted: bool,
+    // Every (address, value) written to memory, when enabled with `enable_write_log`.
+    write_log: Option<Vec<(usize, i64)>>,
+}
+
+impl Clone for Program {
+    /// Clones the program's memory, pointer, and pending inputs. The input source is not cloned:
+    /// it would otherwise be consuming values on behalf of both programs.
+    fn clone(&self) -> Program {
+        Program {
+            opcodes: self.opcodes.clone(),
+            overflow: self.overflow.clone(),
+            memory_len: self.memory_len,
+            initial_opcodes: self.initial_opcodes.clone(),
+            pointer: self.pointer,
+            inputs: self.inputs.clone(),
+            input_source: None,
+            relative_base: self.relative_base,
+            instructions_executed: self.instructions_executed,
+            halted: self.halted,
+            write_log: self.write_log.clone(),
+        }
+    }
 }
 
 impl Program {
     /// Creates a new [`Program`] using the given opcodes as instructions.
     pub fn new(opcodes: Vec<i64>) -> Program {
         Program {
+            initial_opcodes: opcodes.clone(),
+            memory_len: opcodes.len(),
             opcodes,
+            overflow: HashMap::new(),
             pointer: 0,
             inputs: VecDeque::new(),
+            input_source: None,
             relative_base: 0,
+            instructions_executed: 0,
+            halted: false,
+            write_log: None,
         }
     }
 
+    /// Creates a new [`Program`] whose memory is preallocated to hold `capacity` values. Use this for
+    /// programs known to write to many addresses beyond their instructions, avoiding repeated
+    /// reallocation as memory grows.
+    pub fn with_capacity(opcodes: Vec<i64>, capacity: usize) -> Program {
+        let mut program = Program::new(opcodes);
+
+        if capacity > program.opcodes.len() {
+            program.overflow.reserve(capacity - program.opcodes.len());
+        }
+
+        program
+    }
+
     /// Loads the program from a file. The file should consist of a single line of comma-separated
     /// intcodes with an optional newline.
     pub fn from_file(path: &str) -> Result<Program, io::Error> {
         Ok(Program::new(load_intcodes_from_file(path)?))
     }
 
+    /// Restores the program to the state it was in when created: memory contains only the original
+    /// instructions, and the pointer, relative base, input queue, and write log are all cleared.
+    pub fn reset(&mut self) {
+        self.opcodes.clear();
+        self.opcodes.extend_from_slice(&self.initial_opcodes);
+        self.overflow.clear();
+        self.memory_len = self.opcodes.len();
+
+        self.pointer = 0;
+        self.relative_base = 0;
+        self.inputs.clear();
+        self.instructions_executed = 0;
+        self.halted = false;
+
+        if let Some(log) = &mut self.write_log {
+            log.clear();
+        }
+    }
+
+    /// Captures the current memory, pointer, relative base, and pending inputs of the program.
+    pub fn snapshot(&self) -> ProgramSnapshot {
+        ProgramSnapshot {
+            opcodes: self.opcodes.clone(),
+            overflow: self.overflow.clone(),
+            pointer: self.pointer,
+            relative_base: self.relative_base,
+            inputs: self.inputs.clone(),
+            instructions_executed: self.instructions_executed,
+            halted: self.halted,
+        }
+    }
+
+    /// Returns the program to the state captured in `snapshot`. Any input source is kept.
+    pub fn restore(&mut self, snapshot: ProgramSnapshot) {
+        self.opcodes = snapshot.opcodes;
+        self.overflow = snapshot.overflow;
+        self.memory_len = self
+            .overflow
+            .keys()
+            .map(|address| address + 1)
+            .fold(self.opcodes.len(), usize::max);
+        self.pointer = snapshot.pointer;
+        self.relative_base = snapshot.relative_base;
+        self.inputs = snapshot.inputs;
+        self.instructions_executed = snapshot.instructions_executed;
+        self.halted = snapshot.halted;
+    }
+
+    /// Starts recording every write to memory -- including those made by [`Program::poke()`] -- so
+    /// that they may be inspected with [`Program::write_log()`]. Writes made before the log was
+    /// enabled are not recorded.
+    pub fn enable_write_log(&mut self) {
+        if self.write_log.is_none() {
+            self.write_log = Some(Vec::new());
+        }
+    }
+
+    /// Returns each `(address, value)` written to memory, in order, since the write log was
+    /// enabled or the program was reset. Empty if the log is not enabled.
+    pub fn write_log(&self) -> &[(usize, i64)] {
+        match &self.write_log {
+            Some(log) => log,
+            None => &[],
+        }
+    }
+
+    /// Returns how many instructions have been executed since the program was created or reset.
+    /// An Input instruction is not counted until an input is available for it to read, an
+    /// instruction which fails is not counted, and an Exit instruction is counted only once no
+    /// matter how many times the halted program is stepped.
+    pub fn instructions_executed(&self) -> u64 {
+        self.instructions_executed
+    }
+
     /// Jumps to the specified memory `address`.
     fn jump(&mut self, address: usize) {
         self.pointer = address;
@@ -239,20 +556,37 @@ impl Program {
 
     /// Sets a `value` at the given program `address`.
     fn set(&mut self, address: usize, value: i64) {
-        if address > self.opcodes.len() - 1 {
-            self.opcodes.resize(address + 1, 0);
+        if let Some(log) = &mut self.write_log {
+            log.push((address, value));
         }
 
-        self.opcodes[address] = value;
+        match self.opcodes.get_mut(address) {
+            Some(opcode) => *opcode = value,
+            None => {
+                self.overflow.insert(address, value);
+                self.memory_len = self.memory_len.max(address + 1);
+            }
+        }
     }
 
     /// Reads a the value at `address` directly.
     fn read(&self, address: usize) -> i64 {
-        if address > self.opcodes.len() - 1 {
-            return 0;
+        match self.opcodes.get(address) {
+            Some(opcode) => *opcode,
+            None => *self.overflow.get(&address).unwrap_or(&0),
         }
+    }
 
-        self.opcodes[address]
+    /// Returns the value stored in memory at `address`. Addresses beyond the end of the program
+    /// memory contain 0.
+    pub fn peek(&self, address: usize) -> i64 {
+        self.read(address)
+    }
+
+    /// Stores `value` in memory at `address`, growing the program memory if needed. Typically used
+    /// to modify a program before running it.
+    pub fn poke(&mut self, address: usize, value: i64) {
+        self.set(address, value);
     }
 
     /// Places an i64 into the input queue.
@@ -260,141 +594,288 @@ impl Program {
         self.inputs.push_back(input);
     }
 
-    /// Returns the next instruction to be executed, or None if no instructions remain.
+    /// Places each of the `inputs` into the input queue, in order.
+    pub fn push_inputs(&mut self, inputs: &[i64]) {
+        self.inputs.extend(inputs);
+    }
+
+    /// Returns how many inputs are in the queue waiting to be read by the program. Values which may
+    /// be provided by an input source are not counted.
+    pub fn pending_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Sets a function from which the program will read inputs whenever the input queue is empty.
+    /// Values in the queue are always read first. When the function returns `None` the program
+    /// waits for input, exactly as it would with an empty queue and no input source.
+    ///
+    /// The input source is not copied when the program is cloned.
+    pub fn set_input_source<F: FnMut() -> Option<i64> + Send + 'static>(&mut self, source: F) {
+        self.input_source = Some(Box::new(source));
+    }
+
+    /// Places each byte of the ASCII `line` into the input queue, followed by a newline.
+    pub fn push_ascii(&mut self, line: &str) {
+        for byte in line.bytes() {
+            self.push_input(byte as i64);
+        }
+
+        self.push_input('\n' as i64);
+    }
+
+    /// Runs the program until it halts, collecting each ASCII output into a String. Programs which
+    /// output ASCII will often also produce a single non-ASCII value (such as an answer too large
+    /// to be represented as a character); the last such value is returned separately.
+    ///
+    /// Returns [`IntcodeError::NoInput`] if the program needs an input which is not available.
+    pub fn run_ascii(&mut self) -> Result<(String, Option<i64>), IntcodeError> {
+        let mut output = String::new();
+        let mut non_ascii = None;
+
+        for value in self.run_capturing_output()? {
+            if (0..256).contains(&value) {
+                output.push(value as u8 as char);
+            } else {
+                non_ascii = Some(value);
+            }
+        }
+
+        Ok((output, non_ascii))
+    }
+
+    /// Returns the next instruction to be executed, or None if no instructions remain. Returns an
+    /// error if the value at the pointer is not a valid instruction.
     /// TODO: Rename this to front() since it doesn't advance the pointer?
-    fn next(&self) -> Option<InstructionWithMode> {
-        if self.pointer < self.opcodes.len() {
-            return Some(InstructionWithMode::from_intcode(self.read(self.pointer)));
+    fn next(&self) -> Result<Option<InstructionWithMode>, IntcodeError> {
+        if self.pointer < self.memory_len {
+            return Ok(Some(InstructionWithMode::from_intcode(
+                self.read(self.pointer),
+            )?));
         }
 
-        None
+        Ok(None)
     }
 
     /// Takes a single parameter from the program memory. This paramter is always a memory position.
-    fn take_one_param(&self, instruction: &InstructionWithMode) -> usize {
+    fn take_one_param(&self, instruction: &InstructionWithMode) -> Result<usize, IntcodeError> {
         instruction.mode_one.position(self.pointer + 1, &self)
     }
 
     /// Takes two parameters from the program memory. These paramters are always values read from
     /// program memory.
-    fn take_two_params(&self, instruction: &InstructionWithMode) -> (i64, i64) {
-        let value_one = instruction.mode_one.value_at(self.pointer + 1, &self);
+    fn take_two_params(
+        &self,
+        instruction: &InstructionWithMode,
+    ) -> Result<(i64, i64), IntcodeError> {
+        let value_one = instruction.mode_one.value_at(self.pointer + 1, &self)?;
 
-        let value_two = instruction.mode_two.value_at(self.pointer + 2, &self);
+        let value_two = instruction.mode_two.value_at(self.pointer + 2, &self)?;
 
-        (value_one, value_two)
+        Ok((value_one, value_two))
     }
 
     /// Takes three parameters from the program memory. The first two are values read from program
     /// memory, and the third is a memory position.
-    fn take_three_params(&self, instruction: &InstructionWithMode) -> (i64, i64, usize) {
-        let value_one = instruction.mode_one.value_at(self.pointer + 1, &self);
+    fn take_three_params(
+        &self,
+        instruction: &InstructionWithMode,
+    ) -> Result<(i64, i64, usize), IntcodeError> {
+        let value_one = instruction.mode_one.value_at(self.pointer + 1, &self)?;
 
-        let value_two = instruction.mode_two.value_at(self.pointer + 2, &self);
+        let value_two = instruction.mode_two.value_at(self.pointer + 2, &self)?;
 
-        let address = instruction.mode_three.position(self.pointer + 3, &self);
+        let address = instruction.mode_three.position(self.pointer + 3, &self)?;
 
-        (value_one, value_two, address)
+        Ok((value_one, value_two, address))
     }
 
-    /// Runs the program until the next output is yielded, or the program reaches an Exit
-    /// instruction.
-    pub fn run(&mut self) -> ProgramState {
-        while let Some(instruction) = self.next() {
-            match instruction.instruction {
-                Instruction::Add => {
-                    let (left, right, out) = self.take_three_params(&instruction);
-                    self.set(out, left + right);
-                }
-                Instruction::Mul => {
-                    let (left, right, out) = self.take_three_params(&instruction);
-                    self.set(out, left * right);
-                }
-                Instruction::Input => {
-                    let save_to = self.take_one_param(&instruction);
-
-                    let value = match self.inputs.pop_front() {
-                        Some(value) => value,
-                        None => {
-                            // Stops execution awaiting a program input. Calling run will begin
-                            // again from the Input instruction.
-                            return ProgramState::Wait;
-                        }
-                    };
-
-                    self.set(save_to, value);
-                }
-                Instruction::Output => {
-                    // Can't use take_one_param as it returns a usize, which will be invalid if the
-                    // expected value is negative.
-                    let value = instruction.mode_one.value_at(self.pointer + 1, &self);
+    /// Executes exactly one instruction, advancing the pointer to the next. Returns an error if the
+    /// instruction at the pointer is invalid.
+    ///
+    /// An Input instruction which has no input available is not executed, and will be executed
+    /// again by the next step. Once the program reaches an Exit instruction, or the pointer moves
+    /// past the end of program memory, every step returns [`StepResult::Halted`].
+    pub fn step(&mut self) -> Result<StepResult, IntcodeError> {
+        let instruction = match self.next()? {
+            Some(instruction) => instruction,
+            None => return Ok(StepResult::Halted),
+        };
+
+        match instruction.instruction {
+            Instruction::Add => {
+                let (left, right, out) = self.take_three_params(&instruction)?;
+                self.set(out, left + right);
+            }
+            Instruction::Mul => {
+                let (left, right, out) = self.take_three_params(&instruction)?;
+                self.set(out, left * right);
+            }
+            Instruction::Input => {
+                let save_to = self.take_one_param(&instruction)?;
+
+                let value = match self
+                    .inputs
+                    .pop_front()
+                    .or_else(|| self.input_source.as_mut().and_then(|source| source()))
+                {
+                    Some(value) => value,
+                    None => {
+                        // Stops execution awaiting a program input. Stepping again will begin
+                        // from the Input instruction.
+                        return Ok(StepResult::NeedsInput);
+                    }
+                };
 
-                    self.jump_forward(instruction.jump_size());
+                self.set(save_to, value);
+            }
+            Instruction::Output => {
+                // Can't use take_one_param as it returns a usize, which will be invalid if the
+                // expected value is negative.
+                let value = instruction.mode_one.value_at(self.pointer + 1, &self)?;
 
-                    return ProgramState::Output(value);
-                }
-                Instruction::JumpIfTrue => {
-                    let (condition, value) = self.take_two_params(&instruction);
+                self.instructions_executed += 1;
+                self.jump_forward(instruction.jump_size());
 
-                    if condition != 0 {
-                        self.jump(value as usize);
-                    } else {
-                        self.jump_forward(instruction.size());
-                    }
-                }
-                Instruction::JumpIfFalse => {
-                    let (condition, value) = self.take_two_params(&instruction);
+                return Ok(StepResult::Output(value));
+            }
+            Instruction::JumpIfTrue => {
+                let (condition, value) = self.take_two_params(&instruction)?;
 
-                    if condition == 0 {
-                        self.jump(value as usize);
-                    } else {
-                        self.jump_forward(instruction.size());
-                    }
+                if condition != 0 {
+                    self.jump(value as usize);
+                } else {
+                    self.jump_forward(instruction.size());
                 }
-                Instruction::LessThan => {
-                    let (first, second, out) = self.take_three_params(&instruction);
+            }
+            Instruction::JumpIfFalse => {
+                let (condition, value) = self.take_two_params(&instruction)?;
 
-                    if first < second {
-                        self.set(out, 1);
-                    } else {
-                        self.set(out, 0);
-                    }
+                if condition == 0 {
+                    self.jump(value as usize);
+                } else {
+                    self.jump_forward(instruction.size());
                 }
-                Instruction::Equal => {
-                    let (first, second, out) = self.take_three_params(&instruction);
+            }
+            Instruction::LessThan => {
+                let (first, second, out) = self.take_three_params(&instruction)?;
 
-                    if first == second {
-                        self.set(out, 1);
-                    } else {
-                        self.set(out, 0);
-                    }
+                if first < second {
+                    self.set(out, 1);
+                } else {
+                    self.set(out, 0);
                 }
-                Instruction::SetRelativeBase => {
-                    let value = instruction.mode_one.value_at(self.pointer + 1, &self);
-                    self.relative_base = (self.relative_base as i64 + value) as usize;
+            }
+            Instruction::Equal => {
+                let (first, second, out) = self.take_three_params(&instruction)?;
+
+                if first == second {
+                    self.set(out, 1);
+                } else {
+                    self.set(out, 0);
                 }
-                Instruction::Exit => break,
             }
+            Instruction::SetRelativeBase => {
+                let value = instruction.mode_one.value_at(self.pointer + 1, &self)?;
+                self.relative_base += value;
+            }
+            Instruction::Exit => {
+                if !self.halted {
+                    self.halted = true;
+                    self.instructions_executed += 1;
+                }
 
-            self.jump_forward(instruction.jump_size());
+                return Ok(StepResult::Halted);
+            }
         }
 
-        ProgramState::Halt
+        self.instructions_executed += 1;
+        self.jump_forward(instruction.jump_size());
+
+        Ok(StepResult::Continued)
     }
 
-    /// Runs the program until it halts, returning a vector containing all outputs yielded.
-    pub fn run_capturing_output(&mut self) -> Vec<i64> {
+    /// Runs the program until the next output is yielded, the program reaches an Exit
+    /// instruction, or it needs an input which isn't available. Returns an error if the program
+    /// contains an invalid instruction.
+    pub fn run(&mut self) -> Result<ProgramState, IntcodeError> {
+        loop {
+            match self.step()? {
+                StepResult::Continued => {}
+                StepResult::Output(value) => return Ok(ProgramState::Output(value)),
+                StepResult::NeedsInput => return Ok(ProgramState::Wait),
+                StepResult::Halted => return Ok(ProgramState::Halt),
+            }
+        }
+    }
+
+    /// Runs the program until it halts, returning a vector containing all outputs yielded. Returns
+    /// [`IntcodeError::NoInput`] if the program needs an input which is not available.
+    pub fn run_capturing_output(&mut self) -> Result<Vec<i64>, IntcodeError> {
         let mut output = Vec::new();
 
+        self.run_with_output(|value| output.push(value))?;
+
+        Ok(output)
+    }
+
+    /// Returns an iterator which runs the program, yielding each value it outputs. The iterator
+    /// ends when the program halts, or when it needs an input which is not available; more inputs
+    /// may then be pushed and a new iterator created to continue.
+    ///
+    /// Panics if the program contains an invalid instruction.
+    pub fn outputs(&mut self) -> Outputs<'_> {
+        Outputs { program: self }
+    }
+
+    /// Runs the program until it halts, calling `on_output` with each value as it is yielded.
+    /// Returns the number of instructions executed during the run, or
+    /// [`IntcodeError::NoInput`] if the program needs an input which is not available.
+    pub fn run_with_output<F: FnMut(i64)>(
+        &mut self,
+        mut on_output: F,
+    ) -> Result<u64, IntcodeError> {
+        let executed_before = self.instructions_executed;
+
         loop {
-            match self.run() {
-                ProgramState::Output(value) => output.push(value),
-                ProgramState::Wait => panic!("Cannot wait on input with run_capturing_output"),
+            match self.run()? {
+                ProgramState::Output(value) => on_output(value),
+                ProgramState::Wait => return Err(IntcodeError::NoInput),
                 ProgramState::Halt => break,
             }
         }
 
-        output
+        Ok(self.instructions_executed - executed_before)
+    }
+}
+
+/// Runs the `producer` until it halts, pushing each value it outputs onto the input queue of the
+/// `consumer`. The consumer itself is not run. Returns the number of values piped, or
+/// [`IntcodeError::NoInput`] if the producer needs an input which is not available.
+pub fn pipe(producer: &mut Program, consumer: &mut Program) -> Result<usize, IntcodeError> {
+    let mut piped = 0;
+
+    producer.run_with_output(|value| {
+        consumer.push_input(value);
+        piped += 1;
+    })?;
+
+    Ok(piped)
+}
+
+/// Borrows a program and iterates through each of its outputs. Created by [`Program::outputs()`].
+pub struct Outputs<'a> {
+    program: &'a mut Program,
+}
+
+impl Iterator for Outputs<'_> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.program.run() {
+            Ok(ProgramState::Output(value)) => Some(value),
+            Ok(ProgramState::Wait) | Ok(ProgramState::Halt) => None,
+            Err(error) => panic!("{}", error),
+        }
     }
 }
 
@@ -409,11 +890,14 @@ impl Iterator for ProgramIntoIterator {
 
     /// Runs the program until it yields a value, or halts. Next will reutrn Some(i64) when the
     /// program produced a value, and None otheriwse.
+    ///
+    /// Panics if the program contains an invalid instruction.
     fn next(&mut self) -> Option<Self::Item> {
         match self.program.run() {
-            ProgramState::Output(value) => Some(value),
-            ProgramState::Wait => panic!("Cannot wait on input in a Iterator"),
-            ProgramState::Halt => None,
+            Ok(ProgramState::Output(value)) => Some(value),
+            Ok(ProgramState::Wait) => panic!("Cannot wait on input in a Iterator"),
+            Ok(ProgramState::Halt) => None,
+            Err(error) => panic!("{}", error),
         }
     }
 }
@@ -427,60 +911,97 @@ impl IntoIterator for Program {
     }
 }
 
+impl FromStr for Program {
+    type Err = ParseIntcodesError;
+
+    /// Creates a program from a comma-separated list of intcodes.
+    fn from_str(intcodes: &str) -> Result<Self, Self::Err> {
+        Ok(Program::new(parse_intcodes(intcodes)?))
+    }
+}
+
+impl TryFrom<&str> for Program {
+    type Error = ParseIntcodesError;
+
+    fn try_from(intcodes: &str) -> Result<Self, Self::Error> {
+        intcodes.parse()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_decode_instruction() {
-        let instruction = InstructionWithMode::from_intcode(1);
+        let instruction = InstructionWithMode::from_intcode(1).unwrap();
 
         assert_eq!(instruction.instruction, Instruction::Add);
         assert_eq!(instruction.mode_one, ParamMode::Position);
         assert_eq!(instruction.mode_two, ParamMode::Position);
         assert_eq!(instruction.mode_three, ParamMode::Position);
 
-        let instruction = InstructionWithMode::from_intcode(1002);
+        let instruction = InstructionWithMode::from_intcode(1002).unwrap();
 
         assert_eq!(instruction.instruction, Instruction::Mul);
         assert_eq!(instruction.mode_one, ParamMode::Position);
         assert_eq!(instruction.mode_two, ParamMode::Immediate);
         assert_eq!(instruction.mode_three, ParamMode::Position);
 
-        let instruction = InstructionWithMode::from_intcode(2);
+        let instruction = InstructionWithMode::from_intcode(2).unwrap();
 
         assert_eq!(instruction.instruction, Instruction::Mul);
         assert_eq!(instruction.mode_one, ParamMode::Position);
         assert_eq!(instruction.mode_two, ParamMode::Position);
         assert_eq!(instruction.mode_three, ParamMode::Position);
 
-        let instruction = InstructionWithMode::from_intcode(10002);
+        let instruction = InstructionWithMode::from_intcode(10002).unwrap();
 
         assert_eq!(instruction.instruction, Instruction::Mul);
         assert_eq!(instruction.mode_one, ParamMode::Position);
         assert_eq!(instruction.mode_two, ParamMode::Position);
         assert_eq!(instruction.mode_three, ParamMode::Immediate);
 
-        let instruction = InstructionWithMode::from_intcode(11102);
+        let instruction = InstructionWithMode::from_intcode(11102).unwrap();
 
         assert_eq!(instruction.instruction, Instruction::Mul);
         assert_eq!(instruction.mode_one, ParamMode::Immediate);
         assert_eq!(instruction.mode_two, ParamMode::Immediate);
         assert_eq!(instruction.mode_three, ParamMode::Immediate);
 
-        let instruction = InstructionWithMode::from_intcode(99);
+        let instruction = InstructionWithMode::from_intcode(99).unwrap();
 
         assert_eq!(instruction.instruction, Instruction::Exit);
     }
 
+    #[test]
+    fn test_disassemble() {
+        assert_eq!(
+            disassemble(&[1002, 4, 3, 4, 99]),
+            vec!["MUL [4] #3 -> [4]", "HALT"]
+        );
+
+        assert_eq!(
+            disassemble(&[109, -5, 203, 1, 1105, 1, 0, 4, 7, 42, 1]),
+            vec![
+                "ARB #-5",
+                "IN -> R+1",
+                "JT #1 #0",
+                "OUT [7]",
+                "DATA 42",
+                "DATA 1"
+            ]
+        );
+    }
+
     #[test]
     fn test_position_mode_get() {
         let mode = ParamMode::Position;
         let instructions = vec![1, 2, 3, 4, 5, 6];
         let program = Program::new(instructions);
 
-        assert_eq!(mode.value_at(0, &program), 2);
-        assert_eq!(mode.value_at(1, &program), 3);
+        assert_eq!(mode.value_at(0, &program), Ok(2));
+        assert_eq!(mode.value_at(1, &program), Ok(3));
     }
 
     #[test]
@@ -489,8 +1010,8 @@ mod tests {
         let instructions = vec![1, 2, 3, 4, 5, 6];
         let program = Program::new(instructions);
 
-        assert_eq!(mode.value_at(0, &program), 1);
-        assert_eq!(mode.value_at(1, &program), 2);
+        assert_eq!(mode.value_at(0, &program), Ok(1));
+        assert_eq!(mode.value_at(1, &program), Ok(2));
     }
 
     #[test]
@@ -501,21 +1022,21 @@ mod tests {
 
         // Relative base is 0. Read the value at address 0 and add it to the relative base. This
         // gives us index 1, and a value of 2.
-        assert_eq!(mode.value_at(0, &program), 2);
+        assert_eq!(mode.value_at(0, &program), Ok(2));
 
         // Relative base is 0. Read the value at address 1 and add it to the relative base. This
         // gives us index 2, and a value of 3.
-        assert_eq!(mode.value_at(1, &program), 3);
+        assert_eq!(mode.value_at(1, &program), Ok(3));
 
         program.relative_base = 2;
 
         // Relative base is 2. Read the value at address 0 and add it to the relative base. This
         // gives us index 3, and a value of 4.
-        assert_eq!(mode.value_at(0, &program), 4);
+        assert_eq!(mode.value_at(0, &program), Ok(4));
 
         // Relative base is 2. Read the value at address 1 and add it to the relative base. This
         // gives us index 3, and a value of 4.
-        assert_eq!(mode.value_at(1, &program), 5);
+        assert_eq!(mode.value_at(1, &program), Ok(5));
     }
 
     #[test]
@@ -526,7 +1047,7 @@ mod tests {
         let mut program = Program::new(intcodes);
         program.relative_base = 2000;
 
-        program.run();
+        program.run().unwrap();
 
         assert_eq!(program.relative_base, 2019);
 
@@ -538,17 +1059,355 @@ mod tests {
         program.set(1985, 1337);
         program.relative_base = 2000;
 
-        assert_eq!(program.run(), ProgramState::Output(1337));
+        assert_eq!(program.run(), Ok(ProgramState::Output(1337)));
+    }
+
+    #[test]
+    fn test_wait_for_input() {
+        // Sets the relative base to 7, reads an input into R+0, then outputs it.
+        let mut program = Program::new(vec![109, 7, 203, 0, 204, 0, 99, 0]);
+
+        assert_eq!(program.run(), Ok(ProgramState::Wait));
+        assert_eq!(program.pointer, 2);
+
+        // Running again without input continues to wait on the same instruction.
+        assert_eq!(program.run(), Ok(ProgramState::Wait));
+        assert_eq!(program.pointer, 2);
+        assert_eq!(program.relative_base, 7);
+
+        program.push_input(42);
+
+        assert_eq!(program.run(), Ok(ProgramState::Output(42)));
+        assert_eq!(program.read(7), 42);
+        assert_eq!(program.run(), Ok(ProgramState::Halt));
+    }
+
+    #[test]
+    fn test_program_step() {
+        let mut program = Program::new(vec![1002, 4, 3, 4, 33]);
+
+        assert_eq!(program.step(), Ok(StepResult::Continued));
+        assert_eq!(program.opcodes, vec![1002, 4, 3, 4, 99]);
+        assert_eq!(program.pointer, 4);
+
+        assert_eq!(program.step(), Ok(StepResult::Halted));
+        assert_eq!(program.step(), Ok(StepResult::Halted));
+        assert_eq!(program.pointer, 4);
+
+        // Reads an input, then outputs it.
+        let mut program = Program::new(vec![3, 0, 4, 0, 99]);
+
+        assert_eq!(program.step(), Ok(StepResult::NeedsInput));
+        assert_eq!(program.pointer, 0);
+
+        program.push_input(7);
+
+        assert_eq!(program.step(), Ok(StepResult::Continued));
+        assert_eq!(program.step(), Ok(StepResult::Output(7)));
+        assert_eq!(program.step(), Ok(StepResult::Halted));
+    }
+
+    #[test]
+    fn test_instructions_executed() {
+        let mut program = Program::new(vec![1101, 2, 3, 0, 99]);
+        program.run().unwrap();
+
+        assert_eq!(program.instructions_executed(), 2);
+
+        program.reset();
+        assert_eq!(program.instructions_executed(), 0);
+
+        // An Input instruction is counted only once it reads a value.
+        let mut program = Program::new(vec![3, 0, 99]);
+
+        assert_eq!(program.run(), Ok(ProgramState::Wait));
+        assert_eq!(program.instructions_executed(), 0);
+
+        program.push_input(1);
+        program.run().unwrap();
+
+        assert_eq!(program.instructions_executed(), 2);
+
+        // Stepping a halted program doesn't count the Exit instruction again.
+        assert_eq!(program.step(), Ok(StepResult::Halted));
+        assert_eq!(program.run(), Ok(ProgramState::Halt));
+        assert_eq!(program.instructions_executed(), 2);
+
+        // An instruction which fails is not counted.
+        let mut program = Program::new(vec![1101, 2, 3, 0, 1, -1, 0, 0, 99]);
+
+        assert_eq!(program.run(), Err(IntcodeError::NegativeAddress(-1)));
+        assert_eq!(program.instructions_executed(), 1);
     }
 
     #[test]
     fn test_program() {
         let mut program = Program::new(vec![1002, 4, 3, 4, 33]);
-        program.run();
+        program.run().unwrap();
 
         assert_eq!(program.opcodes, vec![1002, 4, 3, 4, 99]);
     }
 
+    #[test]
+    fn test_program_with_capacity() {
+        let mut program = Program::with_capacity(vec![1002, 4, 3, 4, 33], 2048);
+        let capacity = program.overflow.capacity();
+
+        assert!(capacity >= 2043);
+
+        program.set(2047, 1337);
+
+        assert_eq!(program.overflow.capacity(), capacity);
+        assert_eq!(program.read(2047), 1337);
+
+        program.run().unwrap();
+
+        assert_eq!(program.read(4), 99);
+    }
+
+    #[test]
+    fn test_program_clone() {
+        // Outputs each input it receives, forever.
+        let mut program = Program::new(vec![3, 9, 4, 9, 1105, 1, 0, 99, 0, 0]);
+
+        program.push_input(1);
+        assert_eq!(program.run(), Ok(ProgramState::Output(1)));
+
+        let mut cloned = program.clone();
+
+        program.push_input(2);
+        cloned.push_input(3);
+
+        assert_eq!(program.run(), Ok(ProgramState::Output(2)));
+        assert_eq!(cloned.run(), Ok(ProgramState::Output(3)));
+
+        assert_eq!(program.run(), Ok(ProgramState::Wait));
+        assert_eq!(cloned.run(), Ok(ProgramState::Wait));
+    }
+
+    #[test]
+    fn test_program_reset() {
+        let mut program = Program::new(vec![1, 0, 0, 0, 99]);
+
+        program.run().unwrap();
+        assert_eq!(program.opcodes, vec![2, 0, 0, 0, 99]);
+
+        program.push_input(1);
+        program.reset();
+
+        assert_eq!(program.opcodes, vec![1, 0, 0, 0, 99]);
+        assert_eq!(program.pointer, 0);
+        assert!(program.inputs.is_empty());
+
+        program.run().unwrap();
+        assert_eq!(program.opcodes, vec![2, 0, 0, 0, 99]);
+
+        // Memory beyond the initial instructions is discarded.
+        let mut program = Program::new(vec![109, 5, 21101, 1, 2, 10, 99]);
+
+        program.run().unwrap();
+        assert_eq!(program.read(15), 3);
+        assert_eq!(program.relative_base, 5);
+
+        program.reset();
+
+        assert_eq!(program.opcodes, vec![109, 5, 21101, 1, 2, 10, 99]);
+        assert!(program.overflow.is_empty());
+        assert_eq!(program.read(15), 0);
+        assert_eq!(program.relative_base, 0);
+    }
+
+    #[test]
+    fn test_program_sparse_memory() {
+        // Writes 1 + 2 to address 10,000,000 then outputs it.
+        let mut program = Program::new(vec![1101, 1, 2, 10_000_000, 4, 10_000_000, 99]);
+
+        assert_eq!(program.run(), Ok(ProgramState::Output(3)));
+
+        assert_eq!(program.opcodes.len(), 7);
+        assert_eq!(program.overflow.len(), 1);
+        assert_eq!(program.peek(10_000_000), 3);
+        assert_eq!(program.peek(9_999_999), 0);
+
+        // Instructions written beyond the program are executed.
+        let mut program = Program::new(vec![1101, 0, 99, 100, 1105, 1, 100]);
+
+        assert_eq!(program.run(), Ok(ProgramState::Halt));
+        assert_eq!(program.pointer, 100);
+
+        // Unwritten addresses below the highest written address contain 0, which is not a valid
+        // instruction; only moving past the end of memory halts the program.
+        let mut program = Program::new(vec![1101, 0, 99, 20, 1105, 1, 10]);
+
+        assert_eq!(program.run(), Err(IntcodeError::UnknownOpcode(0)));
+        assert_eq!(program.pointer, 10);
+
+        let mut program = Program::new(vec![1101, 0, 99, 20, 1105, 1, 21]);
+
+        assert_eq!(program.run(), Ok(ProgramState::Halt));
+        assert_eq!(program.pointer, 21);
+    }
+
+    #[test]
+    fn test_program_snapshot_and_restore() {
+        // Reads three inputs, outputting each doubled.
+        let mut program = Program::new(vec![
+            3, 30, 1002, 30, 2, 30, 4, 30, 3, 30, 1002, 30, 2, 30, 4, 30, 3, 30, 1002, 30, 2, 30,
+            4, 30, 99,
+        ]);
+
+        program.push_input(1);
+        program.push_input(2);
+        program.push_input(3);
+
+        assert_eq!(program.run(), Ok(ProgramState::Output(2)));
+
+        let snapshot = program.snapshot();
+
+        assert_eq!(program.run_capturing_output(), Ok(vec![4, 6]));
+        assert_ne!(program.snapshot(), snapshot);
+
+        program.restore(snapshot.clone());
+
+        assert_eq!(program.snapshot(), snapshot);
+        assert_eq!(program.run_capturing_output(), Ok(vec![4, 6]));
+    }
+
+    #[test]
+    fn test_program_write_log() {
+        // Writes 1 + 1 to address 4, changing the next instruction to a multiply which writes 5 * 6
+        // to address 0.
+        let mut program = Program::new(vec![1, 1, 1, 4, 99, 5, 6, 0, 99]);
+
+        program.run().unwrap();
+        assert!(program.write_log().is_empty());
+
+        program.reset();
+        program.enable_write_log();
+        program.run().unwrap();
+
+        assert_eq!(program.write_log(), &[(4, 2), (0, 30)]);
+
+        program.reset();
+        assert!(program.write_log().is_empty());
+    }
+
+    #[test]
+    fn test_program_peek_and_poke() {
+        let mut program = Program::new(vec![1, 0, 0, 0, 99]);
+
+        program.poke(1, 4);
+        program.poke(2, 4);
+
+        assert_eq!(program.peek(1), 4);
+        assert_eq!(program.peek(100), 0);
+
+        program.run().unwrap();
+
+        // Adds address 4 to itself (99 + 99).
+        assert_eq!(program.peek(0), 198);
+
+        program.poke(100, 1);
+        assert_eq!(program.peek(100), 1);
+    }
+
+    #[test]
+    fn test_program_push_inputs() {
+        // Reads three inputs, outputting each.
+        let mut program = Program::new(vec![3, 20, 4, 20, 3, 20, 4, 20, 3, 20, 4, 20, 99]);
+
+        program.push_inputs(&[1, 2, 3, 4, 5]);
+        assert_eq!(program.pending_inputs(), 5);
+
+        assert_eq!(program.run_capturing_output(), Ok(vec![1, 2, 3]));
+        assert_eq!(program.pending_inputs(), 2);
+    }
+
+    #[test]
+    fn test_program_input_source() {
+        // Reads three inputs, outputting each.
+        let intcodes = vec![3, 20, 4, 20, 3, 20, 4, 20, 3, 20, 4, 20, 99];
+
+        let mut program = Program::new(intcodes.clone());
+        let mut counter = 0;
+
+        program.set_input_source(move || {
+            counter += 1;
+            Some(counter)
+        });
+
+        assert_eq!(program.run_capturing_output(), Ok(vec![1, 2, 3]));
+
+        // Queued inputs are read before those from the source.
+        let mut program = Program::new(intcodes.clone());
+        let mut counter = 0;
+
+        program.set_input_source(move || {
+            counter += 1;
+            Some(counter)
+        });
+
+        program.push_input(10);
+
+        assert_eq!(program.run_capturing_output(), Ok(vec![10, 1, 2]));
+
+        // The program waits when the source has no more values.
+        let mut program = Program::new(intcodes);
+        let mut values = vec![5];
+
+        program.set_input_source(move || values.pop());
+
+        assert_eq!(program.run(), Ok(ProgramState::Output(5)));
+        assert_eq!(program.run(), Ok(ProgramState::Wait));
+
+        program.push_input(6);
+        assert_eq!(program.run(), Ok(ProgramState::Output(6)));
+    }
+
+    #[test]
+    fn test_program_input_source_not_cloned() {
+        // Outputs each input it receives, forever.
+        let mut program = Program::new(vec![3, 9, 4, 9, 1105, 1, 0, 99, 0, 0]);
+        let mut values = vec![2, 1];
+
+        program.set_input_source(move || values.pop());
+
+        let mut cloned = program.clone();
+
+        assert_eq!(cloned.run(), Ok(ProgramState::Wait));
+
+        // The clone didn't consume any of the original's inputs.
+        assert_eq!(program.run(), Ok(ProgramState::Output(1)));
+        assert_eq!(program.run(), Ok(ProgramState::Output(2)));
+    }
+
+    #[test]
+    fn test_program_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Program>();
+    }
+
+    #[test]
+    fn test_program_ascii() {
+        // Echoes each input until it receives a newline, then outputs 300.
+        let intcodes = vec![
+            3, 100, 4, 100, 1008, 100, 10, 101, 1006, 101, 0, 104, 300, 99,
+        ];
+
+        let mut program = Program::new(intcodes.clone());
+        program.push_ascii("Hello, world!");
+
+        assert_eq!(
+            program.run_ascii(),
+            Ok(("Hello, world!\n".to_string(), Some(300)))
+        );
+
+        let mut program = Program::new(intcodes);
+        program.push_input('a' as i64);
+
+        assert_eq!(program.run_ascii(), Err(IntcodeError::NoInput));
+    }
+
     #[test]
     fn test_program_into_iter() {
         // output value at 1 = 1
@@ -570,11 +1429,122 @@ mod tests {
         // output value at 4 = 3
         // output value at 7 = 2
         let mut program = Program::new(vec![4, 1, 4, 3, 4, 7, 99, 2]);
-        let values = program.run_capturing_output();
+        let values = program.run_capturing_output().unwrap();
 
         assert_eq!(values, vec![1, 3, 2]);
     }
 
+    #[test]
+    fn test_program_run_with_output() {
+        // Outputs a copy of itself.
+        let quine = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+
+        let mut program = Program::new(quine.clone());
+        let mut values = Vec::new();
+
+        let executed = program.run_with_output(|value| values.push(value)).unwrap();
+
+        assert_eq!(values, quine);
+        assert_eq!(values, Program::new(quine).run_capturing_output().unwrap());
+        assert_eq!(executed, program.instructions_executed());
+    }
+
+    #[test]
+    fn test_program_outputs() {
+        // Outputs a copy of itself.
+        let quine = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+
+        let mut program = Program::new(quine.clone());
+
+        assert_eq!(
+            program.outputs().collect::<Vec<i64>>(),
+            Program::new(quine).run_capturing_output().unwrap()
+        );
+
+        assert_eq!(program.outputs().next(), None);
+
+        // Iteration stops while waiting for input, and may be resumed.
+        let mut program = Program::new(vec![3, 9, 4, 9, 104, 7, 1105, 1, 0, 0]);
+
+        assert_eq!(program.outputs().next(), None);
+
+        program.push_input(1);
+        assert_eq!(program.outputs().collect::<Vec<i64>>(), vec![1, 7]);
+
+        program.push_input(2);
+        assert_eq!(program.outputs().collect::<Vec<i64>>(), vec![2, 7]);
+    }
+
+    #[test]
+    fn test_pipe() {
+        let mut producer = Program::new(vec![104, 1, 104, 2, 104, 3, 99]);
+
+        // Reads three inputs, outputting each.
+        let mut consumer = Program::new(vec![3, 20, 4, 20, 3, 20, 4, 20, 3, 20, 4, 20, 99]);
+
+        assert_eq!(pipe(&mut producer, &mut consumer), Ok(3));
+        assert_eq!(consumer.run_capturing_output(), Ok(vec![1, 2, 3]));
+
+        // The producer has halted, so nothing more is piped.
+        assert_eq!(pipe(&mut producer, &mut consumer), Ok(0));
+    }
+
+    #[test]
+    fn test_program_run_capturing_output_no_input() {
+        let mut program = Program::new(vec![3, 0, 4, 0, 99]);
+
+        assert_eq!(program.run_capturing_output(), Err(IntcodeError::NoInput));
+    }
+
+    #[test]
+    fn test_output_at_end_of_tape() {
+        // The tape ends immediately after the output instruction, without an exit.
+        let mut program = Program::new(vec![104, 1234]);
+
+        assert_eq!(program.run(), Ok(ProgramState::Output(1234)));
+        assert_eq!(program.run(), Ok(ProgramState::Halt));
+
+        let mut program = Program::new(vec![4, 1]);
+
+        assert_eq!(program.run_capturing_output(), Ok(vec![1]));
+    }
+
+    #[test]
+    fn test_negative_address() {
+        // Sets the relative base to -5, then saves an input relative to it.
+        let mut program = Program::new(vec![109, -5, 203, 0, 99]);
+        program.push_input(1);
+
+        assert_eq!(program.run(), Err(IntcodeError::NegativeAddress(-5)));
+
+        let mut program = Program::new(vec![4, -1, 99]);
+
+        assert_eq!(program.run(), Err(IntcodeError::NegativeAddress(-1)));
+
+        // A negative relative base is fine so long as the addresses it produces are not.
+        let mut program = Program::new(vec![109, -5, 204, 10, 99, 1234]);
+
+        assert_eq!(program.run(), Ok(ProgramState::Output(1234)));
+    }
+
+    #[test]
+    fn test_unknown_opcode() {
+        let mut program = Program::new(vec![42, 99]);
+
+        assert_eq!(program.run(), Err(IntcodeError::UnknownOpcode(42)));
+    }
+
+    #[test]
+    fn test_invalid_param_mode() {
+        let mut program = Program::new(vec![1301, 0, 0, 0, 99]);
+
+        assert_eq!(program.run(), Err(IntcodeError::InvalidParamMode(3)));
+    }
+
     #[test]
     fn test_load_intcodes_from_file() -> Result<(), io::Error> {
         assert!(load_intcodes_from_file("nope.txt").is_err());
@@ -584,9 +1554,38 @@ mod tests {
             vec![1002, 4, 3, 4, 33]
         );
 
+        let error = load_intcodes_from_file("examples/invalid.txt").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
         Ok(())
     }
 
+    #[test]
+    fn test_parse_intcodes() {
+        assert_eq!(
+            parse_intcodes("1002,4,3,4,33\n"),
+            Ok(vec![1002, 4, 3, 4, 33])
+        );
+        assert_eq!(parse_intcodes("104, -1, 99"), Ok(vec![104, -1, 99]));
+
+        let error = parse_intcodes("1002,4,x,4,33").unwrap_err();
+        assert!(error.to_string().starts_with("Invalid intcode \"x\""));
+    }
+
+    #[test]
+    fn test_program_from_str() {
+        let mut program = "1002,4,3,4,33".parse::<Program>().unwrap();
+        program.run().unwrap();
+
+        assert_eq!(program.opcodes, vec![1002, 4, 3, 4, 99]);
+
+        let mut program = Program::try_from("104,1234,99").unwrap();
+        assert_eq!(program.run_capturing_output(), Ok(vec![1234]));
+
+        assert!("1002,4,x,4,33".parse::<Program>().is_err());
+        assert!(Program::try_from("").is_err());
+    }
+
     #[test]
     fn test_program_from_file() {
         assert!(Program::from_file("nope.txt").is_err());
@@ -595,7 +1594,7 @@ mod tests {
         assert!(program.is_ok());
 
         if let Ok(mut program) = program {
-            program.run();
+            program.run().unwrap();
             assert_eq!(program.opcodes, vec![1002, 4, 3, 4, 99]);
         }
     }